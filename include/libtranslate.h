@@ -0,0 +1,110 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef LIBTRANSLATE_H
+#define LIBTRANSLATE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call, mapped from `Error`.
+typedef enum LtStatus {
+  LT_STATUS_OK = 0,
+  // A pointer was null, or a string was not valid UTF-8.
+  LT_STATUS_INVALID_ARGUMENT = 1,
+  LT_STATUS_INVALID_LANGUAGE = 2,
+  LT_STATUS_UNSUPPORTED_LANGUAGE = 3,
+  LT_STATUS_INVALID_SERVICE = 4,
+  LT_STATUS_NO_AVAILABLE_SERVICE = 5,
+  LT_STATUS_RATE_LIMITED = 6,
+  LT_STATUS_DEADLINE_EXCEEDED = 7,
+  LT_STATUS_AUTH_FAILURE = 8,
+  LT_STATUS_QUOTA_EXHAUSTED = 9,
+  LT_STATUS_LENGTH_LIMIT = 10,
+  LT_STATUS_NETWORK = 11,
+  LT_STATUS_PARSE = 12,
+  LT_STATUS_IO = 13,
+  // The service answered with an unexpected HTTP status.
+  LT_STATUS_STATUS = 14,
+  // Any other error, see `lt_last_error`.
+  LT_STATUS_OTHER = 15,
+  // A bug in libtranslate, the handle should not be used anymore.
+  LT_STATUS_PANIC = 16,
+} LtStatus;
+
+// A detector, created by `lt_detector_new` and destroyed by `lt_detector_free`.
+typedef struct LtDetector LtDetector;
+
+// A translator, created by `lt_translator_new` and destroyed by `lt_translator_free`.
+typedef struct LtTranslator LtTranslator;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a translator using `services`, comma-separated names in priority order, or all of them if null or empty.
+// `timeout_seconds` is the timeout of each request, the default one if not positive, and an invalid argument if infinite.
+//
+// # Safety
+// `services` is null or a NUL-terminated string, `translator` is a valid pointer.
+enum LtStatus lt_translator_new(const char *services,
+                                double timeout_seconds,
+                                struct LtTranslator **translator);
+
+// Destroys a translator, null is ignored.
+//
+// # Safety
+// `translator` is null or was returned by `lt_translator_new`, and is not used afterwards.
+void lt_translator_free(struct LtTranslator *translator);
+
+// Translates `text` from `source` to `target`, language codes such as `en`, `source` may be `auto`.
+// On success `result` is set to the translation, to be freed with `lt_string_free`.
+//
+// # Safety
+// `translator` was returned by `lt_translator_new`, the strings are NUL-terminated, `result` is a valid pointer.
+enum LtStatus lt_translate(struct LtTranslator *translator,
+                           const char *text,
+                           const char *source,
+                           const char *target,
+                           char **result);
+
+// Creates a detector using `services`, like `lt_translator_new`.
+//
+// # Safety
+// `services` is null or a NUL-terminated string, `detector` is a valid pointer.
+enum LtStatus lt_detector_new(const char *services,
+                              double timeout_seconds,
+                              struct LtDetector **detector);
+
+// Destroys a detector, null is ignored.
+//
+// # Safety
+// `detector` is null or was returned by `lt_detector_new`, and is not used afterwards.
+void lt_detector_free(struct LtDetector *detector);
+
+// Detects the language of `text`. On success `language` is set to its code, to be freed with `lt_string_free`.
+//
+// # Safety
+// `detector` was returned by `lt_detector_new`, `text` is NUL-terminated, `language` is a valid pointer.
+enum LtStatus lt_detect(struct LtDetector *detector,
+                        const char *text,
+                        char **language);
+
+// The message of the last error of the calling thread, or null. To be freed with `lt_string_free`.
+char *lt_last_error(void);
+
+// Frees a string returned by libtranslate, null is ignored.
+//
+// # Safety
+// `string` is null or was returned by libtranslate, and is not used afterwards.
+void lt_string_free(char *string);
+
+// The version of libtranslate, a static string that must not be freed.
+const char *lt_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LIBTRANSLATE_H */
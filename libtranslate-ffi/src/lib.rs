@@ -0,0 +1,3 @@
+//! Builds the C API of `libtranslate::ffi` as a shared library, its header is `include/libtranslate.h`.
+
+pub use libtranslate::ffi::*;
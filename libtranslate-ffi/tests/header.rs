@@ -0,0 +1,19 @@
+// The committed header must be what cbindgen generates from `src/ffi.rs`
+#[test]
+fn test_header() {
+    use std::path::Path;
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+    let mut generated = vec![];
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/ffi.rs"))
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+
+    let committed = std::fs::read_to_string(root.join("include/libtranslate.h")).unwrap();
+    assert!(String::from_utf8(generated).unwrap() == committed,
+        "include/libtranslate.h is out of date, run `cbindgen --config cbindgen.toml --output include/libtranslate.h`");
+}
@@ -0,0 +1,133 @@
+use crate::Language;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LanguageSet {
+    All,
+    Only(Vec<Language>),
+    Except(Vec<Language>),
+}
+
+impl LanguageSet {
+    pub fn contains(&self, language: Language) -> bool {
+        match self {
+            LanguageSet::All => true,
+            LanguageSet::Only(languages) => languages.contains(&language),
+            LanguageSet::Except(languages) => !languages.contains(&language),
+        }
+    }
+}
+
+/// What a `TranslatorAPI` can handle, checked by the dispatcher before a request is sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    sources: LanguageSet,
+    targets: LanguageSet,
+    excluded_pairs: Vec<(Language, Language)>,
+    auto_detect: bool,
+    max_length: Option<usize>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            sources: LanguageSet::All,
+            targets: LanguageSet::All,
+            excluded_pairs: vec![],
+            auto_detect: true,
+            max_length: None,
+        }
+    }
+}
+
+impl Capabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sources(mut self, sources: LanguageSet) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    pub fn targets(mut self, targets: LanguageSet) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Pairs that are not supported even though both languages are.
+    pub fn exclude_pair(mut self, source: Language, target: Language) -> Self {
+        self.excluded_pairs.push((source, target));
+        self
+    }
+
+    /// Whether `Language::Auto` is accepted as the source language.
+    pub fn auto_detect(mut self, auto_detect: bool) -> Self {
+        self.auto_detect = auto_detect;
+        self
+    }
+
+    /// The maximum input length in characters.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn supports(&self, source: Language, target: Language) -> bool {
+        if target == Language::Auto || !self.targets.contains(target) {
+            return false;
+        }
+        let source_ok = match source {
+            Language::Auto => self.auto_detect,
+            source => self.sources.contains(source),
+        };
+        source_ok && !self.excluded_pairs.contains(&(source, target))
+    }
+
+    pub fn accepts_length(&self, length: usize) -> bool {
+        self.max_length.is_none_or(|max| length <= max)
+    }
+
+    pub fn supported_sources(&self) -> &LanguageSet {
+        &self.sources
+    }
+
+    pub fn supported_targets(&self) -> &LanguageSet {
+        &self.targets
+    }
+
+    pub fn supports_auto_detect(&self) -> bool {
+        self.auto_detect
+    }
+
+    pub fn max_text_length(&self) -> Option<usize> {
+        self.max_length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_capabilities() {
+        use super::{Capabilities, LanguageSet};
+        use crate::Language;
+
+        let all = Capabilities::new();
+        assert!(all.supports(Language::Auto, Language::English));
+        assert!(!all.supports(Language::English, Language::Auto));
+        assert!(all.accepts_length(usize::MAX));
+
+        let caps = Capabilities::new()
+            .sources(LanguageSet::Only(vec![Language::English, Language::German]))
+            .targets(LanguageSet::Except(vec![Language::Latin]))
+            .exclude_pair(Language::German, Language::French)
+            .auto_detect(false)
+            .max_length(10);
+        assert!(caps.supports(Language::English, Language::French));
+        assert!(!caps.supports(Language::German, Language::French));
+        assert!(!caps.supports(Language::English, Language::Latin));
+        assert!(!caps.supports(Language::Italian, Language::English));
+        assert!(!caps.supports(Language::Auto, Language::English));
+        assert!(caps.accepts_length(10));
+        assert!(!caps.accepts_length(11));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::Result;
+use crate::Error;
+use crate::api::{
+    HttpRequest,
+    HttpResponse,
+    Transport
+};
+
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf
+};
+use std::sync::{
+    Arc,
+    Mutex
+};
+use async_trait::async_trait;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Sends every request and saves the responses, replacing the file.
+    Record,
+    /// Answers from the file only, a request that was not recorded fails with `Error::NotRecorded`.
+    Replay,
+    /// Answers from the file, and records the requests that are not in it yet.
+    Auto,
+}
+
+type Redact = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+struct Interaction {
+    request: HttpRequest,
+    response: HttpResponse,
+}
+
+/// A `Transport` that records responses to a JSON file once and replays them offline afterwards.
+///
+/// Requests are matched on method, URL and body. Secrets such as API keys can be kept out of the
+/// file with `redact`, which is applied to requests before they are matched or saved, and to responses.
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    inner: Option<Arc<dyn Transport>>,
+    redact: Option<Redact>,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Cassette {
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let interactions = load(path.as_ref())?;
+        Ok(Self::with(path.as_ref(), CassetteMode::Replay, None, interactions))
+    }
+
+    pub fn record(path: impl AsRef<Path>, inner: impl Transport + 'static) -> Self {
+        Self::with(path.as_ref(), CassetteMode::Record, Some(Arc::new(inner)), vec![])
+    }
+
+    /// A missing file is started empty.
+    pub fn auto(path: impl AsRef<Path>, inner: impl Transport + 'static) -> Result<Self> {
+        let interactions = match path.as_ref().exists() {
+            true => load(path.as_ref())?,
+            false => vec![],
+        };
+        Ok(Self::with(path.as_ref(), CassetteMode::Auto, Some(Arc::new(inner)), interactions))
+    }
+
+    fn with(path: &Path, mode: CassetteMode, inner: Option<Arc<dyn Transport>>, interactions: Vec<Interaction>) -> Self {
+        Self { path: path.to_path_buf(), mode, inner, redact: None, interactions: Mutex::new(interactions) }
+    }
+
+    pub fn redact(mut self, redact: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.redact = Some(Box::new(redact));
+        self
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    fn redacted(&self, text: &str) -> String {
+        match &self.redact {
+            Some(redact) => redact(text),
+            None => text.to_string(),
+        }
+    }
+
+    fn redacted_request(&self, request: &HttpRequest) -> HttpRequest {
+        let mut redacted = request.clone();
+        redacted.url = self.redacted(&request.url);
+        redacted.body = request.body.as_deref().map(|body| self.redacted(body));
+        redacted
+    }
+
+    fn find(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let interactions = self.interactions.lock().unwrap();
+        interactions.iter().find(|interaction| interaction.request == *request).map(|interaction| interaction.response.clone())
+    }
+
+    fn save(&self, request: HttpRequest, response: &HttpResponse) -> Result<()> {
+        let mut response = response.clone();
+        response.body = self.redacted(&response.body);
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(Interaction { request, response });
+        store(&self.path, &interactions)
+    }
+}
+
+#[async_trait]
+impl Transport for Cassette {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let redacted = self.redacted_request(request);
+        if self.mode != CassetteMode::Record {
+            if let Some(response) = self.find(&redacted) {
+                return Ok(response);
+            }
+        }
+        let Some(inner) = self.inner.as_ref().filter(|_| self.mode != CassetteMode::Replay) else {
+            return Err(Error::NotRecorded(format!("{} {}", redacted.method, redacted.url)));
+        };
+
+        let response = inner.send(request).await?;
+        self.save(redacted, &response)?;
+        Ok(response)
+    }
+}
+
+fn load(path: &Path) -> Result<Vec<Interaction>> {
+    let content = fs::read_to_string(path).map_err(|e| Error::IOError(format!("{}: {}", path.display(), e)))?;
+    let json = serde_json::from_str::<serde_json::Value>(&content).map_err(|e| Error::JSONParsingError(e.to_string()))?;
+    let Some(interactions) = json["interactions"].as_array() else {
+        return Err(Error::JSONParsingError(format!("{}: no `interactions`", path.display())));
+    };
+
+    let mut loaded = vec![];
+    for interaction in interactions {
+        let request = &interaction["request"];
+        let response = &interaction["response"];
+        let (Some(method), Some(url), Some(status), Some(body)) = (
+            request["method"].as_str(),
+            request["url"].as_str(),
+            response["status"].as_u64(),
+            response["body"].as_str(),
+        ) else {
+            return Err(Error::JSONParsingError(format!("{}: malformed interaction {}", path.display(), interaction)));
+        };
+        loaded.push(Interaction {
+            request: HttpRequest {
+                method: method.to_string(),
+                url: url.to_string(),
+                body: request["body"].as_str().map(str::to_string),
+            },
+            response: HttpResponse {
+                status: status as u16,
+                retry_after: response["retry_after"].as_str().map(str::to_string),
+                body: body.to_string(),
+            },
+        });
+    }
+    Ok(loaded)
+}
+
+// Pretty printed, so that recorded files are easy to review
+fn store(path: &Path, interactions: &[Interaction]) -> Result<()> {
+    let interactions: Vec<serde_json::Value> = interactions.iter()
+        .map(|interaction| json!({
+            "request": {
+                "method": interaction.request.method,
+                "url": interaction.request.url,
+                "body": interaction.request.body,
+            },
+            "response": {
+                "status": interaction.response.status,
+                "retry_after": interaction.response.retry_after,
+                "body": interaction.response.body,
+            },
+        }))
+        .collect();
+    let content = serde_json::to_string_pretty(&json!({ "interactions": interactions })).map_err(|e| Error::JSONParsingError(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::IOError(e.to_string()))?;
+    }
+    fs::write(path, content + "\n").map_err(|e| Error::IOError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_cassette() {
+        use crate::{Error, Result};
+        use crate::api::{HttpRequest, HttpResponse, Transport};
+        use super::{Cassette, CassetteMode};
+        use async_trait::async_trait;
+
+        struct Echo;
+
+        #[async_trait]
+        impl Transport for Echo {
+            async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+                Ok(HttpResponse::new(200, &format!("echo {}", request.url())))
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("libtranslate-cassette-{}.json", std::process::id()));
+        let redact = |text: &str| text.replace("secret", "<KEY>");
+
+        let recorder = Cassette::record(&path, Echo).redact(redact);
+        let response = recorder.send(&HttpRequest::get("https://example.com/?key=secret")).await.unwrap();
+        assert_eq!(response.body(), "echo https://example.com/?key=secret");
+        recorder.send(&HttpRequest::post("https://example.com/", "q=1")).await.unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+
+        let player = Cassette::replay(&path).unwrap().redact(redact);
+        assert_eq!(player.mode(), CassetteMode::Replay);
+        let response = player.send(&HttpRequest::get("https://example.com/?key=secret")).await.unwrap();
+        assert_eq!(response.body(), "echo https://example.com/?key=<KEY>");
+        assert!(player.send(&HttpRequest::post("https://example.com/", "q=1")).await.is_ok());
+        assert!(matches!(player.send(&HttpRequest::post("https://example.com/", "q=2")).await, Err(Error::NotRecorded(_))));
+
+        let auto = Cassette::auto(&path, Echo).unwrap();
+        assert_eq!(auto.send(&HttpRequest::get("https://example.com/new")).await.unwrap().body(), "echo https://example.com/new");
+        assert!(Cassette::replay(&path).unwrap().send(&HttpRequest::get("https://example.com/new")).await.is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Cassette::replay(&path), Err(Error::IOError(_))));
+    }
+}
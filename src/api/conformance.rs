@@ -0,0 +1,356 @@
+use crate::Result;
+use crate::Error;
+use crate::Language;
+use crate::api::{
+    DetectorAPI,
+    TranslatorAPI,
+    HttpRequest,
+    HttpResponse,
+    Request,
+    Transport
+};
+
+use std::sync::{
+    Arc,
+    Mutex
+};
+use std::time::{
+    Duration,
+    Instant
+};
+use async_trait::async_trait;
+
+/// Unicode, markup, and characters reserved in URLs, query strings, forms and JSON.
+const ROUND_TRIP_SAMPLES: [&str; 3] = [
+    "Grüße, 世界! 👋",
+    "a&b=c?d#e%f+g/h;i",
+    "<b>\"quoted\" 'text'</b> \\ tab\there",
+];
+
+/// The checks run by `Conformance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// An empty answer to empty input fails with `Error::ReturnedEmptyResult`.
+    EmptyInput,
+    /// The text reaches the service unchanged, and the result comes back unchanged.
+    RoundTrip,
+    /// HTTP error statuses map to the typed `Error` variants.
+    Errors,
+    /// The detected source language is reported for `Language::Auto`.
+    AutoSource,
+    /// A request timing out fails promptly with a retryable error.
+    Timeout,
+}
+
+/// The answers of the service being tested, in its own format, served to it by `Conformance`.
+pub trait ConformanceStub: Send + Sync {
+    /// The answer to `request`, translated into `result` from the detected `source`.
+    fn translation(&self, request: &HttpRequest, result: &str, source: Language) -> HttpResponse;
+
+    /// The answer to `request` when there is nothing to translate.
+    fn empty(&self, request: &HttpRequest) -> HttpResponse;
+}
+
+#[derive(Clone)]
+enum Reply {
+    Translation(String, Language),
+    Empty,
+    Status(u16, Option<&'static str>),
+    Hang(Duration),
+}
+
+// Answers every request with `reply`, and keeps the requests
+struct StubTransport<S> {
+    stub: Arc<S>,
+    reply: Reply,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+#[async_trait]
+impl<S: ConformanceStub> Transport for StubTransport<S> {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        self.requests.lock().unwrap().push(request.clone());
+        match &self.reply {
+            Reply::Translation(result, source) => Ok(self.stub.translation(request, result, *source)),
+            Reply::Empty => Ok(self.stub.empty(request)),
+            Reply::Status(status, retry_after) => {
+                let response = HttpResponse::new(*status, "");
+                Ok(match retry_after {
+                    Some(retry_after) => response.retry_after(retry_after),
+                    None => response,
+                })
+            },
+            // What `ReqwestTransport` does when its timeout expires
+            Reply::Hang(timeout) => {
+                tokio::time::sleep(*timeout).await;
+                Err(Error::ReqwestError("operation timed out".to_string()))
+            },
+        }
+    }
+}
+
+/// A test harness that any `TranslatorAPI` or `DetectorAPI` can be run against, with a local stub
+/// of its service instead of the real one.
+///
+/// ```ignore
+/// Conformance::new(MyStub).translator(&MyTranslator::new()).await.unwrap();
+/// ```
+pub struct Conformance<S> {
+    stub: Arc<S>,
+    skipped: Vec<Check>,
+    timeout: Duration,
+}
+
+impl<S: ConformanceStub + 'static> Conformance<S> {
+    pub fn new(stub: S) -> Self {
+        Self { stub: Arc::new(stub), skipped: vec![], timeout: Duration::from_millis(200) }
+    }
+
+    /// Leaves out a check the service cannot pass, e.g. `Check::AutoSource` for one that never reports the source.
+    pub fn skip(mut self, check: Check) -> Self {
+        self.skipped.push(check);
+        self
+    }
+
+    /// How long the stub takes to time out in `Check::Timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs every check that was not skipped, and returns those that failed with the reason.
+    pub async fn translator(&self, api: &dyn TranslatorAPI) -> std::result::Result<(), Vec<(Check, String)>> {
+        let mut failures = vec![];
+        let mut check = |check: Check, result: std::result::Result<(), String>| {
+            if let Err(reason) = result {
+                failures.push((check, reason));
+            }
+        };
+
+        if !self.skipped.contains(&Check::EmptyInput) {
+            let (result, _) = self.run(Reply::Empty, |request| async move { api.translate(&request, "", Language::Auto, Language::German).await }).await;
+            check(Check::EmptyInput, expect_empty(result));
+        }
+        if !self.skipped.contains(&Check::RoundTrip) {
+            for sample in ROUND_TRIP_SAMPLES {
+                let reply = Reply::Translation(sample.to_string(), Language::English);
+                let (result, requests) = self.run(reply, |request| async move { api.translate(&request, sample, Language::English, Language::German).await }).await;
+                check(Check::RoundTrip, sent(sample, &requests).and_then(|_| match result {
+                    Ok(translation) if translation.result() == sample => Ok(()),
+                    Ok(translation) => Err(format!("{:?} came back as {:?}", sample, translation.result())),
+                    Err(e) => Err(format!("{:?} failed: {}", sample, e)),
+                }));
+            }
+        }
+        if !self.skipped.contains(&Check::Errors) {
+            for (status, retry_after, expected) in error_statuses() {
+                let (result, _) = self.run(Reply::Status(status, retry_after), |request| async move { api.translate(&request, "Hallo", Language::Auto, Language::English).await }).await;
+                check(Check::Errors, expect_error(status, result.map(|_| ()), &expected));
+            }
+        }
+        if !self.skipped.contains(&Check::AutoSource) {
+            let reply = Reply::Translation("Hello".to_string(), Language::German);
+            let (result, _) = self.run(reply, |request| async move { api.translate(&request, "Hallo", Language::Auto, Language::English).await }).await;
+            check(Check::AutoSource, match result {
+                Ok(translation) if translation.source() == Some(Language::German) => Ok(()),
+                Ok(translation) => Err(format!("the source is {:?} instead of German", translation.source())),
+                Err(e) => Err(e.to_string()),
+            });
+        }
+        if !self.skipped.contains(&Check::Timeout) {
+            check(Check::Timeout, self.expect_timeout(|request| async move {
+                api.translate(&request, "Hallo", Language::Auto, Language::English).await.map(|_| ())
+            }).await);
+        }
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures),
+        }
+    }
+
+    /// Like `translator`, through `DetectorAPI::detect`.
+    pub async fn detector(&self, api: &dyn DetectorAPI) -> std::result::Result<(), Vec<(Check, String)>> {
+        let mut failures = vec![];
+        let mut check = |check: Check, result: std::result::Result<(), String>| {
+            if let Err(reason) = result {
+                failures.push((check, reason));
+            }
+        };
+
+        if !self.skipped.contains(&Check::EmptyInput) {
+            let (result, _) = self.run(Reply::Empty, |request| async move { api.detect(&request, "").await }).await;
+            check(Check::EmptyInput, expect_empty(result));
+        }
+        if !self.skipped.contains(&Check::RoundTrip) {
+            for sample in ROUND_TRIP_SAMPLES {
+                let (_, requests) = self.run(Reply::Translation(sample.to_string(), Language::English), |request| async move { api.detect(&request, sample).await }).await;
+                check(Check::RoundTrip, sent(sample, &requests));
+            }
+        }
+        if !self.skipped.contains(&Check::Errors) {
+            for (status, retry_after, expected) in error_statuses() {
+                let (result, _) = self.run(Reply::Status(status, retry_after), |request| async move { api.detect(&request, "Hallo").await }).await;
+                check(Check::Errors, expect_error(status, result.map(|_| ()), &expected));
+            }
+        }
+        if !self.skipped.contains(&Check::AutoSource) {
+            let reply = Reply::Translation("Hello".to_string(), Language::German);
+            let (result, _) = self.run(reply, |request| async move { api.detect(&request, "Hallo").await }).await;
+            check(Check::AutoSource, match result {
+                Ok(detection) if detection.language() == Language::German => Ok(()),
+                Ok(detection) => Err(format!("detected {:?} instead of German", detection.language())),
+                Err(e) => Err(e.to_string()),
+            });
+        }
+        if !self.skipped.contains(&Check::Timeout) {
+            check(Check::Timeout, self.expect_timeout(|request| async move {
+                api.detect(&request, "Hallo").await.map(|_| ())
+            }).await);
+        }
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures),
+        }
+    }
+
+    // Calls the service with a stub answering `reply`, and returns the result with the requests it sent
+    async fn run<R, F>(&self, reply: Reply, call: impl FnOnce(Request) -> F) -> (Result<R>, Vec<HttpRequest>)
+        where F: std::future::Future<Output = Result<R>> {
+
+        let transport = Arc::new(StubTransport { stub: self.stub.clone(), reply: reply, requests: Mutex::new(vec![]) });
+        let result = call(Request::from_arc(transport.clone())).await;
+        let requests = transport.requests.lock().unwrap().clone();
+        (result, requests)
+    }
+
+    async fn expect_timeout<F>(&self, call: impl FnOnce(Request) -> F) -> std::result::Result<(), String>
+        where F: std::future::Future<Output = Result<()>> {
+
+        // Retrying inside the service is left to the dispatcher, a single timeout is all a call may take
+        let limit = self.timeout * 3;
+        let start = Instant::now();
+        let result = tokio::time::timeout(limit, self.run(Reply::Hang(self.timeout), call)).await;
+        match result {
+            Err(_) => Err(format!("still running after {:?}", limit)),
+            Ok((Ok(()), _)) => Err("succeeded although the request timed out".to_string()),
+            Ok((Err(e), _)) if !e.is_retryable() => Err(format!("the timeout gave the non-retryable error {:?}", e)),
+            Ok(_) if start.elapsed() > limit => Err(format!("took {:?}", start.elapsed())),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+fn error_statuses() -> Vec<(u16, Option<&'static str>, Error)> {
+    vec![
+        (429, Some("2"), Error::RateLimited(Some(Duration::from_secs(2)))),
+        (401, None, Error::AuthFailure("401 Unauthorized".to_string())),
+        (403, None, Error::AuthFailure("403 Forbidden".to_string())),
+        (456, None, Error::QuotaExhausted("456".to_string())),
+        (413, None, Error::PayloadTooLarge("413 Payload Too Large".to_string())),
+        (503, None, Error::Status("503 Service Unavailable".to_string())),
+    ]
+}
+
+fn expect_empty<R>(result: Result<R>) -> std::result::Result<(), String> {
+    match result {
+        Err(Error::ReturnedEmptyResult) => Ok(()),
+        Err(e) => Err(format!("{:?} instead of ReturnedEmptyResult", e)),
+        Ok(_) => Err("succeeded instead of ReturnedEmptyResult".to_string()),
+    }
+}
+
+fn expect_error(status: u16, result: Result<()>, expected: &Error) -> std::result::Result<(), String> {
+    match result {
+        // Only the variant matters, except for the retry delay and the status of `Status`
+        Err(e) if std::mem::discriminant(&e) == std::mem::discriminant(expected)
+            && (!matches!(e, Error::RateLimited(_) | Error::Status(_)) || e == *expected) => Ok(()),
+        Err(e) => Err(format!("status {} gave {:?} instead of {:?}", status, e, expected)),
+        Ok(()) => Err(format!("status {} succeeded", status)),
+    }
+}
+
+// Whether `text` was sent unchanged, as a query parameter, a form field, a JSON string, or the whole body
+fn sent(text: &str, requests: &[HttpRequest]) -> std::result::Result<(), String> {
+    let carries = |request: &HttpRequest| {
+        let Ok(mut url) = reqwest::Url::parse(request.url()) else {
+            return false;
+        };
+        if url.query_pairs().any(|(_, value)| value == text) {
+            return true;
+        }
+        let Some(body) = request.body() else {
+            return false;
+        };
+        url.set_query(Some(body));
+        body == text
+            || url.query_pairs().any(|(_, value)| value == text)
+            || serde_json::from_str::<serde_json::Value>(body).is_ok_and(|json| contains_string(&json, text))
+    };
+    match requests.iter().any(carries) {
+        true => Ok(()),
+        false => Err(format!("{:?} was not sent unchanged in {:?}", text, requests)),
+    }
+}
+
+fn contains_string(json: &serde_json::Value, text: &str) -> bool {
+    match json {
+        serde_json::Value::String(value) => value == text,
+        serde_json::Value::Array(values) => values.iter().any(|value| contains_string(value, text)),
+        serde_json::Value::Object(values) => values.values().any(|value| contains_string(value, text)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_conformance() {
+        use crate::{Error, Language, Result};
+        use crate::api::{HttpRequest, HttpResponse, Request, Translation, TranslatorAPI};
+        use super::{Check, Conformance, ConformanceStub};
+        use async_trait::async_trait;
+
+        // A service that answers with its result as plain text, and the source in the status line
+        struct PlainStub;
+
+        impl ConformanceStub for PlainStub {
+            fn translation(&self, _request: &HttpRequest, result: &str, source: Language) -> HttpResponse {
+                HttpResponse::new(200, &format!("{}\n{}", source, result))
+            }
+
+            fn empty(&self, _request: &HttpRequest) -> HttpResponse {
+                HttpResponse::new(200, "en\n")
+            }
+        }
+
+        struct Plain {
+            encode: bool,
+        }
+
+        #[async_trait]
+        impl TranslatorAPI for Plain {
+            async fn translate(&self, request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                let url = match self.encode {
+                    true => reqwest::Url::parse_with_params("https://example.com/", &[("q", text)]).unwrap().to_string(),
+                    false => format!("https://example.com/?q={}", text),
+                };
+                let body = request.get(&url).await?;
+                let (source, result) = body.split_once('\n').unwrap();
+                if result.is_empty() {
+                    return Err(Error::ReturnedEmptyResult);
+                }
+                let source = source.parse().ok();
+                Ok(Translation { source: source, source_tag: None, target: target, result: result.to_string(), attempts: vec![] })
+            }
+        }
+
+        Conformance::new(PlainStub).translator(&Plain { encode: true }).await.unwrap();
+
+        // Reserved characters cut the query string short
+        let failures = Conformance::new(PlainStub).translator(&Plain { encode: false }).await.unwrap_err();
+        assert!(!failures.is_empty());
+        assert!(failures.iter().all(|(check, _)| *check == Check::RoundTrip));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::Result;
 use crate::Error;
 use crate::Language;
+use crate::LanguageTag;
 use crate::error::constant;
 use crate::api::{
     DetectorAPI,
@@ -8,7 +9,9 @@ use crate::api::{
     DetectorAPIContainer,
     TranslatorAPIContainer,
     Request,
-    Translation
+    Translation,
+    Detection,
+    Capabilities
 };
 
 use serde_json;
@@ -16,16 +19,36 @@ use scraper;
 use regex;
 use async_trait::async_trait;
 
-// const TEXT_LIMIT: usize = 5_000;
+// The text is sent in the query string, longer input is usually rejected
+const TEXT_LIMIT: usize = 5_000;
+// The `maxlength` of the input field of the mobile page
+const MOBILE_TEXT_LIMIT: usize = 2_048;
+
+// Google answers with its own codes such as `iw`, `jw` or `zh-CN`, which are mapped to the closest `Language`
+fn parse_source(code: &str) -> (Option<Language>, Option<LanguageTag>) {
+    let Ok(tag) = LanguageTag::parse(code) else {
+        return (Language::from(code), None);
+    };
+    (Language::from(code).or_else(|| tag.to_language()), Some(tag))
+}
 
-// fn text_limit_check(text: &str) -> Result<usize> {
-//     println!("{}", text);
-//     let count = text.chars().count();
-//     if count > TEXT_LIMIT {
-//         return Err(Error::LengthLimit(count));
-//     }
-//     Ok(count)
-// }
+// The URL of `api` with `params` percent-encoded, so that reserved characters in the text reach the service
+fn query(api: &str, params: &[(&str, &str)]) -> String {
+    reqwest::Url::parse_with_params(api, params).unwrap().to_string()
+}
+
+// Google's error page, which the mobile page is sometimes answered with despite a success status:
+// <title>Error 400 (Bad Request)!!1</title> in the head and <div id="af-error-page2"><main id="af-error-container" role="main">...</main></div>
+// in the body, e.g. <p><b>400.</b> <ins>That’s an error.</ins><p>The server cannot process the request because it is malformed.
+fn error_page(document: &scraper::Html) -> Option<Error> {
+    let page = scraper::Selector::parse("#af-error-page2").ok()?;
+    document.select(&page).next()?;
+    let title = scraper::Selector::parse("title").ok()?;
+    let title: String = document.select(&title).next()?.text().collect();
+    let reg = regex::Regex::new(r#"^Error (\d+) \((.*)\)"#).unwrap();
+    let captures = reg.captures(title.trim())?;
+    Some(Error::from_status(captures[1].parse().ok()?, &captures[2], None))
+}
 
 // https://translate.google.com/m?hl=en&sl={source}&tl={target}&q={text}
 // The response is a HTML page: </style></head><body><div class="header"><div class="logo-image"></div><div class="logo-text">Translate</div></div><div class="languages-container"><div class="sl-and-tl"><a href="./m?sl=auto&amp;tl=zh-CN&amp;q=Hello%20world%21&amp;mui=sl&amp;hl=en">Detect language</a> → <a href="./m?sl=auto&amp;tl=zh-CN&amp;q=Hello%20world%21&amp;mui=tl&amp;hl=en">Chinese (Simplified)</a></div></div><div class="input-container"><form action="/m"><input type="hidden" name="sl" value="auto"><input type="hidden" name="tl" value="zh-CN"><input type="hidden" name="hl" value="en"><input type="text" aria-label="Source text" name="q" class="input-field" maxlength="2048" value="Hello world!"><div class="translate-button-container"><input type="submit" value="Translate" class="translate-button"></div></form></div><div class="result-container">你好世界！</div><div class="links-container"><ul><li><a href="https://www.google.com/m?hl=en">Google home</a></li><li><a href="https://www.google.com/tools/feedback/survey/xhtml?productId=95112&hl=en">Send feedback</a></li><li><a href="https://www.google.com/intl/en/policies">Privacy and terms</a></li><li><a href="./full">Switch to full site</a></li></ul></div></body></html>
@@ -44,13 +67,11 @@ impl Into<TranslatorAPIContainer> for API_MobileGoogleTranslate {
 #[async_trait]
 impl TranslatorAPI for API_MobileGoogleTranslate {
     async fn translate(&self, request: &Request, text: &str, source: Language, target: Language) -> Result<Translation> {
-        // todo: 5k limit
         static API: &str = "https://translate.google.com/m";
 
         let sl = Language::abbreviation(&source).unwrap();
         let tl = Language::abbreviation(&target).unwrap();
-        let params = format!("?hl=en&sl={}&tl={}&q={}", sl, tl, text);
-        let url = format!("{}{}", API, params);
+        let url = query(API, &[("hl", "en"), ("sl", sl), ("tl", tl), ("q", text)]);
         let body = request.get(&url).await?;
 
         let selector = scraper::Selector::parse("div.result-container");
@@ -59,6 +80,9 @@ impl TranslatorAPI for API_MobileGoogleTranslate {
         };
 
         let document = scraper::Html::parse_document(&body);
+        if let Some(e) = error_page(&document) {
+            return Err(e);
+        }
         let Some(element) = document.select(&selector).next() else {
             return Err(Error::HTMLParsingError("no <div class=\"result-container\"> element".to_string()));
         };
@@ -68,32 +92,21 @@ impl TranslatorAPI for API_MobileGoogleTranslate {
         };
 
         let Some(value) = result.value().as_text() else {
-            let selector = scraper::Selector::parse("div");
-            let Ok(selector) = selector else {
-                return Err(Error::HTMLParsingError(selector.unwrap_err().to_string()));
-            };
-            for main in document.select(&selector) {
-                let Some(id) = main.attr("id") else {
-                    continue
-                };
-                if "af-error-page2" != id {
-                    continue;
-                }
-                // HTML snippet: <main id=\"af-error-container\" role=\"main\"><a href=//www.google.com><span id=logo aria-label=Google role=img></span></a><p><b>400.</b> <ins>That’s an error.</ins><p>The server cannot process the request because it is malformed. It should not be retried. <ins>That’s all we know.</ins></main>
-                let reg = regex::Regex::new(r#"<title>Error (\d+) \((.*)\)!!.*</title>"#).unwrap();
-                if let Some(captures) = reg.captures(&main.html()) {
-                    return Err(Error::Status(captures[1].to_string() + " " + &captures[2].to_string()));
-                }
-            }
             return Err(Error::UnexpectedResult(format!("[{}:{}]{}, HTML: {body}", file!(), line!(), constant::UNEXPECTED_ERROR_STRING)));
         };
 
         Ok(Translation {
             source: None,
+            source_tag: None,
             target: target,
             result: value.to_string(),
+            attempts: vec![],
         })
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new().max_length(MOBILE_TEXT_LIMIT)
+    }
 }
 
 
@@ -120,7 +133,7 @@ impl Into<TranslatorAPIContainer> for API_GoogleDictionaryChromeExtension {
 impl DetectorAPI for API_GoogleDictionaryChromeExtension {
     async fn language(&self, request: &Request, text: &str) -> Result<Language> {
         let result = self.translate(request, text, Language::Auto, Language::English).await?;
-        Ok(result.source.unwrap())
+        result.detected()
     }
 }
 
@@ -131,8 +144,7 @@ impl TranslatorAPI for API_GoogleDictionaryChromeExtension {
 
         let sl = Language::abbreviation(&source).unwrap();
         let tl = Language::abbreviation(&target).unwrap();
-        let params = format!("?client=dict-chrome-ex&sl={}&tl={}&q={}", sl, tl, text);
-        let url = format!("{}{}", API, params);
+        let url = query(API, &[("client", "dict-chrome-ex"), ("sl", sl), ("tl", tl), ("q", text)]);
         let body = request.get(&url).await?;
 
         let json = serde_json::from_str::<serde_json::Value>(&body);
@@ -154,10 +166,13 @@ impl TranslatorAPI for API_GoogleDictionaryChromeExtension {
                     return Err(Error::ReturnedEmptyResult);
                 }
 
+                let (source, source_tag) = parse_source(sl);
                 return Ok(Translation {
-                    source: Language::from(sl),
+                    source: source,
+                    source_tag: source_tag,
                     target: target,
                     result: result.to_string(),
+                    attempts: vec![],
                 })
             },
             Err(e) => {
@@ -165,6 +180,10 @@ impl TranslatorAPI for API_GoogleDictionaryChromeExtension {
             }
         }
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new().max_length(TEXT_LIMIT)
+    }
 }
 
 
@@ -196,19 +215,34 @@ impl Into<TranslatorAPIContainer> for API_GoogleTranslateExtensions {
 impl DetectorAPI for API_GoogleTranslateExtensions {
     async fn language(&self, request: &Request, text: &str) -> Result<Language> {
         let result = self.translate(request, text, Language::Auto, Language::English).await?;
-        Ok(result.source.unwrap())
+        result.detected()
+    }
+
+    async fn detect(&self, request: &Request, text: &str) -> Result<Detection> {
+        let (result, confidence) = self.single(request, text, Language::Auto, Language::English).await?;
+        Ok(Detection::new(result.detected()?, confidence))
     }
 }
 
 #[async_trait]
 impl TranslatorAPI for API_GoogleTranslateExtensions {
     async fn translate(&self, request: &Request, text: &str, source: Language, target: Language) -> Result<Translation> {
+        Ok(self.single(request, text, source, target).await?.0)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new().max_length(TEXT_LIMIT)
+    }
+}
+
+impl API_GoogleTranslateExtensions {
+    // Returns the translation and the confidence of the detected source language (`json[6]`)
+    async fn single(&self, request: &Request, text: &str, source: Language, target: Language) -> Result<(Translation, f64)> {
         static API: &str = "https://translate.googleapis.com/translate_a/single";
 
         let sl = Language::abbreviation(&source).unwrap();
         let tl = Language::abbreviation(&target).unwrap();
-        let params = format!("?client=gtx&dt=t&sl={}&tl={}&q={}", sl, tl, text);
-        let url = format!("{}{}", API, params);
+        let url = query(API, &[("client", "gtx"), ("dt", "t"), ("sl", sl), ("tl", tl), ("q", text)]);
         let body = request.get(&url).await?;
 
         let json = serde_json::from_str::<serde_json::Value>(&body);
@@ -246,12 +280,16 @@ impl TranslatorAPI for API_GoogleTranslateExtensions {
                 }
 
                 let sl = json[8][0][0].as_str().unwrap();
+                let confidence = json[6].as_f64().unwrap_or(1.0);
 
-                return Ok(Translation {
-                    source: Language::from(sl),
+                let (source, source_tag) = parse_source(sl);
+                return Ok((Translation {
+                    source: source,
+                    source_tag: source_tag,
                     target: target,
                     result: result.to_string(),
-                })
+                    attempts: vec![],
+                }, confidence))
             },
             Err(e) => {
                 return Err(Error::JSONParsingError(e.to_string()));
@@ -271,6 +309,132 @@ impl TranslatorAPI for API_GoogleTranslateExtensions {
 mod tests {
     use std::time::Duration;
 
+    #[test]
+    fn test_parse_source() {
+        use super::parse_source;
+        use crate::Language;
+
+        assert_eq!(parse_source("zh-CN").0, Some(Language::SimpleChinese));
+        assert_eq!(parse_source("iw").0, Some(Language::Hebrew));
+        assert_eq!(parse_source("pt-BR").0, Some(Language::Portuguese));
+
+        let (language, tag) = parse_source("jw");
+        assert_eq!(language, None);
+        assert_eq!(tag.unwrap().to_string(), "jv");
+    }
+
+    #[test]
+    fn test_query() {
+        use super::query;
+
+        // `&`, `#` and `+` would otherwise end the text, start a fragment or turn into a space
+        assert_eq!(query("https://translate.google.com/m", &[("tl", "de"), ("q", "a&b #c+d%")]), "https://translate.google.com/m?tl=de&q=a%26b+%23c%2Bd%25");
+    }
+
+    #[tokio::test]
+    async fn test_offline_parsing() {
+        use crate::{Error, Language, Result};
+        use crate::api::{DetectorAPI, HttpRequest, HttpResponse, Request, Transport, TranslatorAPI};
+        use super::{API_GoogleDictionaryChromeExtension, API_GoogleTranslateExtensions, API_MobileGoogleTranslate};
+        use async_trait::async_trait;
+
+        // Answers every request with the same body
+        struct Canned(&'static str);
+
+        #[async_trait]
+        impl Transport for Canned {
+            async fn send(&self, _request: &HttpRequest) -> Result<HttpResponse> {
+                Ok(HttpResponse::new(200, self.0))
+            }
+        }
+
+        let request = Request::new(Canned(r#"<html><body><div class="result-container">Hallo Welt!</div></body></html>"#));
+        let translation = API_MobileGoogleTranslate{}.translate(&request, "Hello world!", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.result(), "Hallo Welt!");
+        let request = Request::new(Canned(r#"<html><head><title>Error 403 (Forbidden)!!1</title></head><body><div id="af-error-page2"></div></body></html>"#));
+        assert!(matches!(API_MobileGoogleTranslate{}.translate(&request, "Hello", Language::English, Language::German).await, Err(Error::AuthFailure(_))));
+
+        let request = Request::new(Canned(r#"[["Hallo Welt!","en"]]"#));
+        let translation = API_GoogleDictionaryChromeExtension{}.translate(&request, "Hello world!", Language::Auto, Language::German).await.unwrap();
+        assert_eq!(translation.source(), Some(Language::English));
+        let request = Request::new(Canned(r#"[["","en"]]"#));
+        assert!(matches!(API_GoogleDictionaryChromeExtension{}.translate(&request, "", Language::Auto, Language::German).await, Err(Error::ReturnedEmptyResult)));
+
+        let request = Request::new(Canned(r#"[[["Hallo ","Hello ",null,null,10],["Welt!","world!",null,null,10]],null,"en",null,null,null,0.9,[],[["en"],null,[0.9],["en"]]]"#));
+        let translation = API_GoogleTranslateExtensions{}.translate(&request, "Hello world!", Language::Auto, Language::German).await.unwrap();
+        assert_eq!(translation.result(), "Hallo Welt!");
+        let detection = API_GoogleTranslateExtensions{}.detect(&request, "Hello world!").await.unwrap();
+        assert_eq!(detection.language(), Language::English);
+        assert_eq!(detection.confidence(), 0.9);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_conformance() {
+        use crate::Language;
+        use crate::api::{Check, Conformance, ConformanceStub, HttpRequest, HttpResponse};
+        use super::{API_GoogleDictionaryChromeExtension, API_GoogleTranslateExtensions, API_MobileGoogleTranslate};
+
+        struct MobileStub;
+
+        impl ConformanceStub for MobileStub {
+            fn translation(&self, _request: &HttpRequest, result: &str, _source: Language) -> HttpResponse {
+                let result = result.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                HttpResponse::new(200, &format!(r#"<html><body><div class="result-container">{}</div></body></html>"#, result))
+            }
+
+            fn empty(&self, _request: &HttpRequest) -> HttpResponse {
+                HttpResponse::new(200, r#"<html><body><div class="result-container"></div></body></html>"#)
+            }
+        }
+
+        struct ChromeExtensionStub;
+
+        impl ConformanceStub for ChromeExtensionStub {
+            fn translation(&self, _request: &HttpRequest, result: &str, source: Language) -> HttpResponse {
+                HttpResponse::new(200, &serde_json::json!([[result, source.to_string()]]).to_string())
+            }
+
+            fn empty(&self, _request: &HttpRequest) -> HttpResponse {
+                HttpResponse::new(200, r#"[["","en"]]"#)
+            }
+        }
+
+        struct ExtensionsStub;
+
+        impl ConformanceStub for ExtensionsStub {
+            fn translation(&self, _request: &HttpRequest, result: &str, source: Language) -> HttpResponse {
+                let source = source.to_string();
+                HttpResponse::new(200, &serde_json::json!([[[result, "", null, null, 10]], null, source, null, null, null, 1, [], [[source], null, [1], [source]]]).to_string())
+            }
+
+            fn empty(&self, _request: &HttpRequest) -> HttpResponse {
+                HttpResponse::new(200, r#"[null,null,"en",null,null,null,0,[],[["en"],null,[0],["en"]]]"#)
+            }
+        }
+
+        // The mobile page does not report the source language
+        Conformance::new(MobileStub).skip(Check::AutoSource).translator(&API_MobileGoogleTranslate{}).await.unwrap();
+        Conformance::new(ChromeExtensionStub).translator(&API_GoogleDictionaryChromeExtension{}).await.unwrap();
+        Conformance::new(ChromeExtensionStub).detector(&API_GoogleDictionaryChromeExtension{}).await.unwrap();
+        Conformance::new(ExtensionsStub).translator(&API_GoogleTranslateExtensions{}).await.unwrap();
+        Conformance::new(ExtensionsStub).detector(&API_GoogleTranslateExtensions{}).await.unwrap();
+    }
+
+    // Replays the responses recorded in `tests/cassettes/google/recorded`, set `LIBTRANSLATE_RECORD` to record them.
+    // Without a recording, the hand-written responses in `tests/cassettes/google/synthetic` are replayed instead.
+    fn cassette(name: &str) -> crate::api::Request {
+        use crate::api::{Cassette, ReqwestTransport, Request};
+
+        let dir = format!("{}/tests/cassettes/google", env!("CARGO_MANIFEST_DIR"));
+        let recorded = format!("{}/recorded/{}.json", dir, name);
+        match std::env::var_os("LIBTRANSLATE_RECORD") {
+            Some(_) => Request::new(Cassette::record(recorded, ReqwestTransport::new(Duration::from_millis(30_000), &Default::default()).unwrap())),
+            None if std::path::Path::new(&recorded).exists() => Request::new(Cassette::replay(recorded).unwrap()),
+            None => Request::new(Cassette::replay(format!("{}/synthetic/{}.json", dir, name)).unwrap()),
+        }
+    }
+
     #[tokio::test]
     #[allow(non_snake_case)]
     async fn test_API_MobileGoogleTranslate() {
@@ -278,14 +442,26 @@ mod tests {
         use super::API_MobileGoogleTranslate;
         use super::TranslatorAPI;
         use crate::Language;
-        use crate::api::Request;
 
         let api = API_MobileGoogleTranslate{};
-        let request = Request::new(Duration::from_millis(30_000)).unwrap();
+        let request = cassette("mobile_google_translate");
+
+        assert!(api.translate(&request, "Hello world!", Language::Auto, Language::SimpleChinese).await.is_ok());
+
+        assert!(matches!(api.translate(&request, "", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
+    }
 
-        assert!(matches!(api.translate(&request, &"Hello world!", Language::Auto, Language::SimpleChinese).await, Ok(_)));
+    // Google cannot be made to answer with its error page, so this always replays the synthetic one
+    #[tokio::test]
+    async fn test_mobile_error_page() {
+        use crate::{Error, Language};
+        use crate::api::{Cassette, Request, TranslatorAPI};
+        use super::API_MobileGoogleTranslate;
 
-        assert!(matches!(api.translate(&request, &"", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
+        let path = format!("{}/tests/cassettes/google/synthetic/mobile_google_translate.json", env!("CARGO_MANIFEST_DIR"));
+        let request = Request::new(Cassette::replay(path).unwrap());
+        let result = API_MobileGoogleTranslate{}.translate(&request, "Too many", Language::Auto, Language::SimpleChinese).await;
+        assert_eq!(result.unwrap_err(), Error::RateLimited(None));
     }
 
     #[tokio::test]
@@ -295,30 +471,28 @@ mod tests {
         use super::API_GoogleDictionaryChromeExtension;
         use super::TranslatorAPI;
         use crate::Language;
-        use crate::api::Request;
 
         let api = API_GoogleDictionaryChromeExtension{};
-        let request = Request::new(Duration::from_millis(30_000)).unwrap();
+        let request = cassette("google_dictionary_chrome_extension");
 
-        assert!(matches!(api.translate(&request, &"Hello world!", Language::Auto, Language::SimpleChinese).await, Ok(_)));
+        assert!(api.translate(&request, "Hello world!", Language::Auto, Language::SimpleChinese).await.is_ok());
 
-        assert!(matches!(api.translate(&request, &"", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
+        assert!(matches!(api.translate(&request, "", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
     }
 
     #[tokio::test]
     #[allow(non_snake_case)]
     async fn test_API_GoogleTranslateExtensions() {
         use crate::Error;
-        use crate::api::Request;
         use super::API_GoogleTranslateExtensions;
         use super::TranslatorAPI;
         use crate::Language;
 
         let api = API_GoogleTranslateExtensions{};
-        let request = Request::new(Duration::from_millis(30_000)).unwrap();
+        let request = cassette("google_translate_extensions");
 
-        assert!(matches!(api.translate(&request, &"Hello world!", Language::Auto, Language::SimpleChinese).await, Ok(_)));
+        assert!(api.translate(&request, "Hello world!", Language::Auto, Language::SimpleChinese).await.is_ok());
 
-        assert!(matches!(api.translate(&request, &"", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
+        assert!(matches!(api.translate(&request, "", Language::Auto, Language::SimpleChinese).await, Err(Error::ReturnedEmptyResult)));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+/// How the HTTP client that talks to the services is set up.
+///
+/// With several proxies or user agents, requests rotate through every pair of them, so that the unauthenticated
+/// endpoints do not see every request coming from the same address and client.
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    pub(crate) proxies: Vec<String>,
+    pub(crate) user_agents: Vec<String>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) http2_only: bool,
+}
+
+impl HttpConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An `http://`, `https://` or `socks5://` proxy, each call adds one to the pool.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxies.push(url.to_string());
+        self
+    }
+
+    /// Each call adds a user agent to the rotation.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agents.push(user_agent.to_string());
+        self
+    }
+
+    /// A header sent with every request.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Trusts a PEM encoded CA certificate in addition to the system ones.
+    pub fn root_certificate(mut self, pem: &[u8]) -> Self {
+        self.root_certificates.push(pem.to_vec());
+        self
+    }
+
+    /// Speaks HTTP/2 right away instead of negotiating it, for servers known to support it.
+    ///
+    /// This is HTTP/2 with prior knowledge: it is also used over plain `http://`, and requests to a server
+    /// that only speaks HTTP/1 fail instead of falling back.
+    pub fn http2_only(mut self, http2_only: bool) -> Self {
+        self.http2_only = http2_only;
+        self
+    }
+}
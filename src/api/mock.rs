@@ -0,0 +1,318 @@
+use crate::Result;
+use crate::Error;
+use crate::Language;
+use crate::api::{
+    DetectorAPI,
+    TranslatorAPI,
+    Request,
+    Translation,
+    Detection,
+    Capabilities
+};
+
+use std::collections::VecDeque;
+use std::sync::{
+    Arc,
+    Mutex
+};
+use std::time::Duration;
+use async_trait::async_trait;
+
+// The replies of a mock: scripted ones first, in order, then the fallback forever
+struct Script<T> {
+    replies: VecDeque<Result<T>>,
+    fallback: Option<Result<T>>,
+    latency: Duration,
+    calls: usize,
+}
+
+impl<T: Clone> Script<T> {
+    fn new() -> Self {
+        Self { replies: VecDeque::new(), fallback: None, latency: Duration::ZERO, calls: 0 }
+    }
+
+    // `None` when the mock has nothing configured and answers with its default
+    fn next(&mut self) -> Option<Result<T>> {
+        self.calls += 1;
+        self.replies.pop_front().or_else(|| self.fallback.clone())
+    }
+}
+
+fn status_error(status: u16) -> Error {
+    let reason = reqwest::StatusCode::from_u16(status).ok().and_then(|status| status.canonical_reason()).unwrap_or_default();
+    Error::from_status(status, reason, None)
+}
+
+/// A `TranslatorAPI` that answers with scripted translations, errors and status codes, without any request.
+///
+/// Register it with `TranslatorBuilder::service` to exercise failover in tests. Clones share their script,
+/// so a clone kept by the test can check `calls` after the original was handed to the builder.
+/// Without any reply configured, the text is returned unchanged.
+#[derive(Clone)]
+pub struct MockTranslator {
+    script: Arc<Mutex<Script<String>>>,
+    source: Option<Language>,
+    capabilities: Capabilities,
+}
+
+impl MockTranslator {
+    pub fn new() -> Self {
+        Self { script: Arc::new(Mutex::new(Script::new())), source: None, capabilities: Capabilities::default() }
+    }
+
+    /// Answers every call with `result`, once the scripted replies are used up.
+    pub fn translation(self, result: &str) -> Self {
+        self.script.lock().unwrap().fallback = Some(Ok(result.to_string()));
+        self
+    }
+
+    /// Fails every call with `error`, once the scripted replies are used up.
+    pub fn error(self, error: Error) -> Self {
+        self.script.lock().unwrap().fallback = Some(Err(error));
+        self
+    }
+
+    /// Fails every call like a service answering with the HTTP `status`, e.g. `429` gives `Error::RateLimited`.
+    pub fn status(self, status: u16) -> Self {
+        self.error(status_error(status))
+    }
+
+    /// Answers the next call with `result`, scripted replies are used in the order they were added.
+    pub fn then_translation(self, result: &str) -> Self {
+        self.script.lock().unwrap().replies.push_back(Ok(result.to_string()));
+        self
+    }
+
+    pub fn then_error(self, error: Error) -> Self {
+        self.script.lock().unwrap().replies.push_back(Err(error));
+        self
+    }
+
+    pub fn then_status(self, status: u16) -> Self {
+        self.then_error(status_error(status))
+    }
+
+    /// How long every call takes.
+    pub fn latency(self, latency: Duration) -> Self {
+        self.script.lock().unwrap().latency = latency;
+        self
+    }
+
+    /// The source language reported with the translations, for calls with `Language::Auto`.
+    pub fn source(mut self, source: Language) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// How many times the mock was called, including the calls that failed or were cancelled.
+    pub fn calls(&self) -> usize {
+        self.script.lock().unwrap().calls
+    }
+}
+
+#[async_trait]
+impl TranslatorAPI for MockTranslator {
+    async fn translate(&self, _request: &Request, text: &str, source: Language, target: Language) -> Result<Translation> {
+        let (reply, latency) = {
+            let mut script = self.script.lock().unwrap();
+            (script.next(), script.latency)
+        };
+        tokio::time::sleep(latency).await;
+
+        let result = reply.unwrap_or_else(|| Ok(text.to_string()))?;
+        let source = match source {
+            Language::Auto => self.source,
+            source => Some(source),
+        };
+        Ok(Translation::new(source, target, &result))
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities.clone()
+    }
+}
+
+/// A `DetectorAPI` that answers with scripted languages, errors and status codes, without any request.
+///
+/// Register it with `DetectorBuilder::service`, clones share their script like those of `MockTranslator`.
+/// Without any reply configured, every text is detected as English.
+#[derive(Clone)]
+pub struct MockDetector {
+    script: Arc<Mutex<Script<Language>>>,
+    confidence: f64,
+}
+
+impl MockDetector {
+    pub fn new() -> Self {
+        Self { script: Arc::new(Mutex::new(Script::new())), confidence: 1.0 }
+    }
+
+    /// Answers every call with `language`, once the scripted replies are used up.
+    pub fn language(self, language: Language) -> Self {
+        self.script.lock().unwrap().fallback = Some(Ok(language));
+        self
+    }
+
+    pub fn error(self, error: Error) -> Self {
+        self.script.lock().unwrap().fallback = Some(Err(error));
+        self
+    }
+
+    pub fn status(self, status: u16) -> Self {
+        self.error(status_error(status))
+    }
+
+    pub fn then_language(self, language: Language) -> Self {
+        self.script.lock().unwrap().replies.push_back(Ok(language));
+        self
+    }
+
+    pub fn then_error(self, error: Error) -> Self {
+        self.script.lock().unwrap().replies.push_back(Err(error));
+        self
+    }
+
+    pub fn then_status(self, status: u16) -> Self {
+        self.then_error(status_error(status))
+    }
+
+    pub fn latency(self, latency: Duration) -> Self {
+        self.script.lock().unwrap().latency = latency;
+        self
+    }
+
+    /// The confidence reported by `detect`.
+    pub fn confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub fn calls(&self) -> usize {
+        self.script.lock().unwrap().calls
+    }
+}
+
+#[async_trait]
+impl DetectorAPI for MockDetector {
+    async fn language(&self, _request: &Request, _text: &str) -> Result<Language> {
+        let (reply, latency) = {
+            let mut script = self.script.lock().unwrap();
+            (script.next(), script.latency)
+        };
+        tokio::time::sleep(latency).await;
+        reply.unwrap_or(Ok(Language::English))
+    }
+
+    async fn detect(&self, request: &Request, text: &str) -> Result<Detection> {
+        Ok(Detection::new(self.language(request, text).await?, self.confidence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_mock_translator() {
+        use crate::{Error, Language, Strategy, Translator};
+        use super::MockTranslator;
+
+        let primary = MockTranslator::new().then_status(503).translation("Hallo");
+        let fallback = MockTranslator::new().translation("Hallo!");
+        let mut t = Translator::builder()
+            .strategy(Strategy::Mix(vec!["primary".to_string(), "fallback".to_string()]))
+            .selection(crate::Selection::Priority)
+            .service("primary", primary.clone(), 100)
+            .service("fallback", fallback.clone(), 100)
+            .build()
+            .unwrap();
+
+        // The scripted 503 fails over to the next service
+        let translation = t.translate("Hello", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.result(), "Hallo!");
+        assert_eq!(translation.source(), Some(Language::English));
+        assert!(matches!(translation.attempts()[0].error(), Some(Error::Status(status)) if status == "503 Service Unavailable"));
+        assert_eq!((primary.calls(), fallback.calls()), (1, 1));
+
+        let mut t = Translator::builder()
+            .strategy(Strategy::Single("google.API_MobileGoogleTranslate".to_string()))
+            .service("google.API_MobileGoogleTranslate", MockTranslator::new().status(429), 100)
+            .build()
+            .unwrap();
+        assert!(matches!(t.translate("Hello", Language::English, Language::German).await, Err(Error::NoAvailableService { .. })));
+
+        let mut t = Translator::builder()
+            .strategy(Strategy::Single("mock".to_string()))
+            .service("mock", MockTranslator::new().status(501), 100)
+            .build()
+            .unwrap();
+        let Err(Error::NoAvailableService { attempts, .. }) = t.translate("Hello", Language::English, Language::German).await else {
+            panic!("expected NoAvailableService");
+        };
+        assert_eq!(attempts[0].error(), Some(&Error::UnsupportedDirection(Language::English, Language::German)));
+
+        assert!(matches!(Translator::builder()
+            .strategy(Strategy::Single("missing".to_string()))
+            .service("mock", MockTranslator::new(), 100)
+            .build(), Err(Error::InvalidServiceName)));
+    }
+
+    #[tokio::test]
+    async fn test_mock_detector() {
+        use crate::{Detector, Error, Language, Strategy};
+        use super::MockDetector;
+
+        let mut d = Detector::builder()
+            .strategy(Strategy::Mix(vec!["a".to_string(), "b".to_string()]))
+            .service("a", MockDetector::new().language(Language::German).confidence(0.9), 100)
+            .service("b", MockDetector::new().language(Language::German), 100)
+            .build()
+            .unwrap();
+        assert_eq!(d.language("Hallo").await.unwrap(), Language::German);
+        let consensus = d.consensus("Hallo").await.unwrap();
+        assert_eq!(consensus.language(), Language::German);
+        assert!(!consensus.disagreement());
+
+        let mock = MockDetector::new().then_error(Error::ReturnedEmptyResult).language(Language::French);
+        let mut d = Detector::builder()
+            .strategy(Strategy::Single("mock".to_string()))
+            .service("mock", mock.clone(), 100)
+            .build()
+            .unwrap();
+        assert!(d.language("Bonjour").await.is_err());
+        assert_eq!(mock.calls(), 1);
+
+        // A tie goes to the service with the higher priority, whatever the order of the registry
+        for _ in 0..10 {
+            let mut d = Detector::builder()
+                .strategy(Strategy::Mix(vec!["b".to_string(), "a".to_string()]))
+                .service("a", MockDetector::new().language(Language::French), 100)
+                .service("b", MockDetector::new().language(Language::German), 100)
+                .build()
+                .unwrap();
+            let consensus = d.consensus("Hallo").await.unwrap();
+            assert_eq!(consensus.language(), Language::German);
+            assert_eq!(consensus.votes()[0].0, "b");
+        }
+
+        // Detectors that all fail report their errors
+        let mut d = Detector::builder()
+            .strategy(Strategy::Mix(vec!["a".to_string(), "b".to_string()]))
+            .service("a", MockDetector::new().status(503), 100)
+            .service("b", MockDetector::new().error(Error::ReturnedEmptyResult), 100)
+            .build()
+            .unwrap();
+        match d.consensus("Hallo").await {
+            Err(Error::NoAvailableService { attempts, last_errors, .. }) => {
+                assert_eq!(attempts.len(), 2);
+                assert_eq!(last_errors.len(), 2);
+                assert_eq!(last_errors[0].0, "a");
+                assert!(matches!(last_errors[1].1, Error::ReturnedEmptyResult));
+            },
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+}
@@ -3,25 +3,209 @@
 pub mod google;
 pub mod azure;
 pub mod custom;
+mod capabilities;
+mod cassette;
+#[cfg(feature = "testing")]
+mod conformance;
+mod http_config;
+#[cfg(feature = "testing")]
+mod mock;
+mod transport;
+
+pub use self::capabilities::{
+    Capabilities,
+    LanguageSet
+};
+pub use self::cassette::{
+    Cassette,
+    CassetteMode
+};
+pub use self::http_config::HttpConfig;
+#[cfg(feature = "testing")]
+pub use self::conformance::{
+    Conformance,
+    ConformanceStub,
+    Check
+};
+#[cfg(feature = "testing")]
+pub use self::mock::{
+    MockTranslator,
+    MockDetector
+};
+pub use self::transport::{
+    Transport,
+    ReqwestTransport,
+    HttpRequest,
+    HttpResponse
+};
 
 use crate::Language;
+use crate::LanguageTag;
 use crate::Result;
 use crate::Error;
+use crate::Attempt;
 
 use std::sync::Arc;
+use std::collections::HashMap;
 use std::time::Duration;
 use async_trait::async_trait;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Translation {
     source: Option<Language>,
+    source_tag: Option<LanguageTag>,
     target: Language,
     result: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    attempts: Vec<Attempt>,
+}
+
+// The attempts describe how a translation was obtained, not the translation itself
+impl PartialEq for Translation {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.source_tag == other.source_tag
+            && self.target == other.target
+            && self.result == other.result
+    }
+}
+
+impl Translation {
+    /// A translation into `target`, for services of your own. `source` is the language the service reported, if any.
+    pub fn new(source: Option<Language>, target: Language, result: &str) -> Self {
+        Self { source, source_tag: source.map(LanguageTag::from), target, result: result.to_string(), attempts: vec![] }
+    }
+
+    /// The source language, if the service reported one.
+    pub fn source(&self) -> Option<Language> {
+        self.source
+    }
+
+    /// The source language exactly as reported by the service, also kept when it has no `Language` variant.
+    pub fn source_tag(&self) -> Option<&LanguageTag> {
+        self.source_tag.as_ref()
+    }
+
+    pub fn target(&self) -> Language {
+        self.target
+    }
+
+    /// The translated text.
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    pub fn into_result(self) -> String {
+        self.result
+    }
+
+    /// The requests sent to obtain this translation, in order, including those that failed.
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+
+    pub(crate) fn prepend_attempts(mut self, mut attempts: Vec<Attempt>) -> Translation {
+        attempts.append(&mut self.attempts);
+        self.attempts = attempts;
+        self
+    }
+
+    // Joins the translations of two consecutive chunks of a text
+    pub(crate) fn join(mut self, separator: &str, next: Translation) -> Translation {
+        self.result.push_str(separator);
+        self.result.push_str(&next.result);
+        self.source = self.source.or(next.source);
+        self.source_tag = self.source_tag.or(next.source_tag);
+        self.attempts.extend(next.attempts);
+        self
+    }
+
+    // Puts back the whitespace around a segment of a stream
+    pub(crate) fn surround(mut self, prefix: &str, suffix: &str) -> Translation {
+        self.result.insert_str(0, prefix);
+        self.result.push_str(suffix);
+        self
+    }
+
+    // The detected source language, or `UnsupportedLanguage` with the reported tag
+    pub(crate) fn detected(&self) -> Result<Language> {
+        match (self.source, &self.source_tag) {
+            (Some(language), _) => Ok(language),
+            (None, Some(tag)) => Err(Error::UnsupportedLanguage(tag.to_string())),
+            (None, None) => Err(Error::UnsupportedLanguage(String::new())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Detection {
+    language: Language,
+    confidence: f64,
+}
+
+impl Detection {
+    /// `confidence` is clamped to `0.0..=1.0`, NaN counts as `0.0`.
+    pub fn new(language: Language, confidence: f64) -> Self {
+        let confidence = if confidence.is_nan() { 0.0 } else { confidence.clamp(0.0, 1.0) };
+        Self { language, confidence }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Confidence reported by the service, in the range `0.0..=1.0`.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+}
+
+/// The outcome of asking several detectors about the same text.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Consensus {
+    language: Language,
+    confidence: f64,
+    disagreement: bool,
+    votes: Vec<(String, Detection)>,
+}
+
+impl Consensus {
+    pub(crate) fn new(language: Language, confidence: f64, disagreement: bool, votes: Vec<(String, Detection)>) -> Self {
+        Self { language, confidence, disagreement, votes }
+    }
+
+    /// The language with the highest weighted score.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Share of the total weighted score held by `language`.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// `true` if the services that answered did not all return the same language.
+    pub fn disagreement(&self) -> bool {
+        self.disagreement
+    }
+
+    /// Every successful answer, keyed by service name.
+    pub fn votes(&self) -> &[(String, Detection)] {
+        &self.votes
+    }
 }
 
 #[async_trait]
 pub trait DetectorAPI: Sync + Send {
     async fn language(&self, request: &Request, text: &str) -> Result<Language>;
+
+    /// Services that report a confidence score should override this, the default is fully confident.
+    async fn detect(&self, request: &Request, text: &str) -> Result<Detection> {
+        Ok(Detection::new(self.language(request, text).await?, 1.0))
+    }
 }
 
 #[derive(Clone)]
@@ -40,13 +224,29 @@ impl DetectorAPI for DetectorAPIContainer {
     async fn language(&self, request: &Request, text: &str) -> Result<Language> {
         self.inner.language(request, text).await
     }
+
+    async fn detect(&self, request: &Request, text: &str) -> Result<Detection> {
+        self.inner.detect(request, text).await
+    }
 }
 
 #[async_trait]
 pub trait TranslatorAPI: Sync + Send {
     async fn translate(&self, request: &Request, text: &str, source: Language, target: Language) -> Result<Translation>;
+
+    /// Like `translate`, with the options given to this service by `TranslateOptions::provider_option`.
+    /// Services that have no options can ignore them, which is the default.
+    async fn translate_with(&self, request: &Request, text: &str, source: Language, target: Language,
+        _options: &HashMap<String, String>) -> Result<Translation> {
+        self.translate(request, text, source, target).await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct TranslatorAPIContainer {
     inner: Arc<dyn TranslatorAPI>
 }
@@ -60,45 +260,57 @@ impl TranslatorAPIContainer {
 #[async_trait]
 impl TranslatorAPI for TranslatorAPIContainer {
     async fn translate(&self, request: &Request, text: &str, source: Language, target: Language) -> Result<Translation> {
-        self.inner.translate(request, text, source, target).await
+        match self.inner.translate(request, text, source, target).await {
+            // The languages of a 501, see `Error::from_status`
+            Err(Error::UnsupportedDirection(Language::Auto, Language::Auto)) => Err(Error::UnsupportedDirection(source, target)),
+            result => result,
+        }
+    }
+
+    async fn translate_with(&self, request: &Request, text: &str, source: Language, target: Language,
+        options: &HashMap<String, String>) -> Result<Translation> {
+        match self.inner.translate_with(request, text, source, target, options).await {
+            // The languages of a 501, see `Error::from_status`
+            Err(Error::UnsupportedDirection(Language::Auto, Language::Auto)) => Err(Error::UnsupportedDirection(source, target)),
+            result => result,
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
     }
 }
-pub(crate) struct Request {
-    client: reqwest::Client,
+/// The HTTP client handed to the services, sends requests through a `Transport`.
+#[derive(Clone)]
+pub struct Request {
+    transport: Arc<dyn Transport>,
 }
 
 impl Request {
-    pub(crate) fn new(timeout: Duration) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .build();
-
-        match client {
-            Ok(client) => { Ok(Self { client }) },
-            Err(e) => { return Err(Error::ReqwestError(e.to_string())); }
-        }
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self { transport: Arc::new(transport) }
     }
 
-    pub(crate) async fn get(&self, url: &str) -> Result<String> {
-        let rsp = self.client
-            .get(url)
-            .send()
-            .await;
-        match rsp {
-            Ok(rsp) => {
-                let 0..=399 = rsp.status().as_u16() else {
-                    return Err(Error::Status(rsp.status().to_string()));
-                };
-
-                let body = rsp.text().await;
-                let Ok(body) = body else {
-                    return Err(Error::ReqwestError(body.unwrap_err().to_string()));
-                };
-
-                Ok(body)
-            },
-            Err(e) => Err(Error::ReqwestError(e.to_string())),
-        }
+    pub(crate) fn from_arc(transport: Arc<dyn Transport>) -> Self {
+        Self { transport }
+    }
+
+    pub(crate) fn with_config(timeout: Duration, config: &HttpConfig) -> Result<Self> {
+        Ok(Self::new(ReqwestTransport::new(timeout, config)?))
+    }
+
+    /// The body of the response, error statuses are turned into errors with `Error::from_status`.
+    pub async fn get(&self, url: &str) -> Result<String> {
+        self.send(&HttpRequest::get(url)).await
+    }
+
+    pub async fn send(&self, request: &HttpRequest) -> Result<String> {
+        let rsp = self.transport.send(request).await?;
+        let 0..=399 = rsp.status() else {
+            let reason = reqwest::StatusCode::from_u16(rsp.status()).ok().and_then(|status| status.canonical_reason()).unwrap_or_default();
+            return Err(Error::from_status(rsp.status(), reason, rsp.retry_after_header()));
+        };
+        Ok(rsp.into_body())
     }
 }
 
@@ -106,11 +318,37 @@ impl Request {
 mod tests {
     use std::time::Duration;
 
+    #[tokio::test]
+    async fn test_transport() {
+        use crate::Error;
+        use crate::Result;
+        use super::{HttpRequest, HttpResponse, Request, Transport};
+        use async_trait::async_trait;
+
+        struct Fixed(HttpResponse);
+
+        #[async_trait]
+        impl Transport for Fixed {
+            async fn send(&self, _request: &HttpRequest) -> Result<HttpResponse> {
+                Ok(self.0.clone())
+            }
+        }
+
+        let request = Request::new(Fixed(HttpResponse::new(200, "body")));
+        assert_eq!(request.get("https://example.com").await.unwrap(), "body");
+
+        let request = Request::new(Fixed(HttpResponse::new(429, "").retry_after("3")));
+        assert!(matches!(request.get("https://example.com").await, Err(Error::RateLimited(Some(d))) if d == Duration::from_secs(3)));
+
+        let request = Request::new(Fixed(HttpResponse::new(503, "")));
+        assert!(matches!(request.get("https://example.com").await, Err(Error::Status(status)) if status == "503 Service Unavailable"));
+    }
+
     #[tokio::test]
     async fn test_request() {
         use crate::Error;
         use super::Request;
-        let request = Request::new(Duration::from_millis(30_000)).unwrap();
+        let request = Request::with_config(Duration::from_millis(30_000), &Default::default()).unwrap();
 
         assert!(matches!(request.get("https://translate.google").await.unwrap_err(), Error::ReqwestError(_)));
 
@@ -0,0 +1,202 @@
+use crate::Result;
+use crate::Error;
+use crate::api::HttpConfig;
+
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering
+};
+use std::time::Duration;
+use async_trait::async_trait;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpRequest {
+    pub(crate) method: String,
+    pub(crate) url: String,
+    pub(crate) body: Option<String>,
+}
+
+impl HttpRequest {
+    pub fn get(url: &str) -> Self {
+        Self { method: "GET".to_string(), url: url.to_string(), body: None }
+    }
+
+    pub fn post(url: &str, body: &str) -> Self {
+        Self { method: "POST".to_string(), url: url.to_string(), body: Some(body.to_string()) }
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub(crate) status: u16,
+    pub(crate) retry_after: Option<String>,
+    pub(crate) body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self { status, retry_after: None, body: body.to_string() }
+    }
+
+    /// The value of the `Retry-After` header.
+    pub fn retry_after(mut self, retry_after: &str) -> Self {
+        self.retry_after = Some(retry_after.to_string());
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn retry_after_header(&self) -> Option<&str> {
+        self.retry_after.as_deref()
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn into_body(self) -> String {
+        self.body
+    }
+}
+
+/// Sends the HTTP requests of the services, `ReqwestTransport` by default.
+///
+/// Implement it to use another client, add middleware, or answer requests in-process in tests.
+/// Error statuses are returned as responses, they are turned into errors by `Request`.
+#[async_trait]
+pub trait Transport: Sync + Send {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse>;
+}
+
+pub struct ReqwestTransport {
+    // One client per proxy, or a single one without proxies
+    clients: Vec<reqwest::Client>,
+    user_agents: Vec<reqwest::header::HeaderValue>,
+    next: AtomicUsize,
+}
+
+impl ReqwestTransport {
+    pub fn new(timeout: Duration, config: &HttpConfig) -> Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| Error::InvalidHttpConfig(e.to_string());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(&e))?;
+            let value = reqwest::header::HeaderValue::from_str(value).map_err(|e| invalid(&e))?;
+            headers.append(name, value);
+        }
+        let user_agents = config.user_agents.iter()
+            .map(|user_agent| reqwest::header::HeaderValue::from_str(user_agent).map_err(|e| invalid(&e)))
+            .collect::<Result<Vec<_>>>()?;
+        let certificates = config.root_certificates.iter()
+            .map(|pem| reqwest::Certificate::from_pem(pem).map_err(|e| invalid(&e)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let build = |proxy: Option<&String>| -> Result<reqwest::Client> {
+            let mut builder = reqwest::Client::builder()
+                .timeout(timeout)
+                .default_headers(headers.clone());
+            if let Some(proxy) = proxy {
+                builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| invalid(&e))?);
+            }
+            if let Some(max) = config.pool_max_idle_per_host {
+                builder = builder.pool_max_idle_per_host(max);
+            }
+            if let Some(timeout) = config.pool_idle_timeout {
+                builder = builder.pool_idle_timeout(timeout);
+            }
+            for certificate in &certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            if config.http2_only {
+                builder = builder.http2_prior_knowledge();
+            }
+            builder.build().map_err(|e| Error::ReqwestError(e.to_string()))
+        };
+
+        let clients = match config.proxies.is_empty() {
+            true => vec![build(None)?],
+            false => config.proxies.iter().map(|proxy| build(Some(proxy))).collect::<Result<Vec<_>>>()?,
+        };
+        Ok(Self { clients, user_agents, next: AtomicUsize::new(0) })
+    }
+
+    // The client and user agent of the `n`th request: the user agent moves on once every proxy has been used,
+    // so that every pair of proxy and user agent comes up in turn
+    fn rotation(&self, n: usize) -> (usize, Option<usize>) {
+        let user_agent = (!self.user_agents.is_empty()).then(|| n / self.clients.len() % self.user_agents.len());
+        (n % self.clients.len(), user_agent)
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let (client, user_agent) = self.rotation(self.next.fetch_add(1, Ordering::Relaxed));
+        let client = &self.clients[client];
+        let method = reqwest::Method::from_bytes(request.method().as_bytes()).map_err(|e| Error::ReqwestError(e.to_string()))?;
+        let mut builder = client.request(method, request.url());
+        if let Some(body) = request.body() {
+            builder = builder.body(body.to_string());
+        }
+        if let Some(user_agent) = user_agent {
+            builder = builder.header(reqwest::header::USER_AGENT, self.user_agents[user_agent].clone());
+        }
+
+        let rsp = builder.send().await.map_err(|e| Error::ReqwestError(e.to_string()))?;
+        let status = rsp.status().as_u16();
+        let retry_after = rsp.headers().get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let body = rsp.text().await.map_err(|e| Error::ReqwestError(e.to_string()))?;
+        Ok(HttpResponse { status, retry_after, body })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn test_http_config() {
+        use crate::Error;
+        use crate::api::HttpConfig;
+        use super::ReqwestTransport;
+
+        let config = HttpConfig::new()
+            .proxy("http://127.0.0.1:8080")
+            .proxy("socks5://127.0.0.1:1080")
+            .user_agent("libtranslate")
+            .header("Accept-Language", "en")
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(30));
+        let transport = ReqwestTransport::new(Duration::from_secs(1), &config).unwrap();
+        assert_eq!(transport.clients.len(), 2);
+        assert_eq!(transport.user_agents.len(), 1);
+        assert_eq!(transport.rotation(3), (1, Some(0)));
+
+        // With as many proxies as user agents, each proxy still gets every user agent
+        let transport = ReqwestTransport::new(Duration::from_secs(1), &config.clone().user_agent("other")).unwrap();
+        let rotation: Vec<_> = (0..4).map(|n| transport.rotation(n)).collect();
+        assert_eq!(rotation, [(0, Some(0)), (1, Some(0)), (0, Some(1)), (1, Some(1))]);
+        assert_eq!(ReqwestTransport::new(Duration::from_secs(1), &HttpConfig::new()).unwrap().rotation(5), (0, None));
+
+        let invalid = |config: HttpConfig| matches!(ReqwestTransport::new(Duration::from_secs(1), &config), Err(Error::InvalidHttpConfig(_)));
+        assert!(invalid(HttpConfig::new().header("bad header", "value")));
+        assert!(invalid(HttpConfig::new().user_agent("bad\nagent")));
+        assert!(invalid(HttpConfig::new().root_certificate(b"not a certificate")));
+        assert!(invalid(HttpConfig::new().proxy("")));
+    }
+}
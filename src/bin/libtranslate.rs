@@ -0,0 +1,354 @@
+use libtranslate::{
+    Detector,
+    Language,
+    Strategy,
+    Translator
+};
+
+use std::io::{
+    BufRead,
+    Read,
+    Write
+};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use clap::{
+    Args,
+    Parser,
+    Subcommand,
+    ValueEnum
+};
+use serde_json::json;
+
+/// Translates and detects the language of text from the command line.
+#[derive(Parser)]
+#[command(name = "libtranslate", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Translates the arguments, or standard input without any.
+    Translate {
+        #[arg(conflicts_with = "lines")]
+        text: Vec<String>,
+        #[command(flatten)]
+        languages: Languages,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Detects the language of the arguments, or of standard input without any.
+    Detect {
+        #[arg(conflicts_with = "lines")]
+        text: Vec<String>,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Translates a text file, to standard output or `--output`.
+    File {
+        path: PathBuf,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        languages: Languages,
+        // The file is translated as a whole into plain text, so neither `--format` nor `--lines` applies
+        #[command(flatten)]
+        services: Services,
+    },
+    /// Lists the language codes accepted by `--from` and `--to`.
+    ListLanguages {
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Lists the services accepted by `--strategy`.
+    ListServices {
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Serves the API of LibreTranslate over HTTP.
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:5000")]
+        listen: String,
+        /// How many translations are cached, `0` disables the cache.
+        #[arg(long, default_value_t = 1_000)]
+        cache_size: usize,
+        /// How many requests are handled at once, each worker keeps the health of its own services.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        workers: u16,
+        #[command(flatten)]
+        services: Services,
+    },
+}
+
+#[derive(Args)]
+struct Languages {
+    /// The source language code, detected if left out.
+    #[arg(short, long, default_value = "auto", value_parser = parse_language)]
+    from: Language,
+    /// The target language code.
+    #[arg(short, long, value_parser = parse_language)]
+    to: Language,
+}
+
+#[derive(Args)]
+struct Services {
+    /// Comma-separated services to use, in priority order, all of them if left out.
+    #[arg(short, long, value_delimiter = ',')]
+    strategy: Vec<String>,
+    /// Timeout of each request, in seconds.
+    #[arg(long, value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+}
+
+#[derive(Args)]
+struct Options {
+    #[command(flatten)]
+    services: Services,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Handles standard input line by line, writing each answer as soon as it is known.
+    #[arg(short, long)]
+    lines: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    /// One JSON object per answer.
+    Json,
+}
+
+fn parse_language(code: &str) -> Result<Language, String> {
+    code.parse().map_err(|e: libtranslate::Error| e.to_string())
+}
+
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    let seconds: f64 = seconds.parse().map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())
+}
+
+impl Services {
+    fn strategy(&self) -> Strategy {
+        match self.strategy.len() {
+            0 => Strategy::Default,
+            1 => Strategy::Single(self.strategy[0].clone()),
+            _ => Strategy::Mix(self.strategy.clone()),
+        }
+    }
+
+    fn translator(&self) -> libtranslate::Result<Translator> {
+        let mut builder = Translator::builder().strategy(self.strategy());
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build()
+    }
+
+    fn detector(&self) -> libtranslate::Result<Detector> {
+        let mut builder = Detector::builder().strategy(self.strategy());
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build()
+    }
+}
+
+// The arguments joined by spaces, or the whole standard input
+fn input(text: &[String]) -> std::io::Result<String> {
+    if !text.is_empty() {
+        return Ok(text.join(" "));
+    }
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    Ok(input.trim_end_matches(['\r', '\n']).to_string())
+}
+
+enum Failure {
+    Library(libtranslate::Error),
+    Io(std::io::Error),
+}
+
+impl From<libtranslate::Error> for Failure {
+    fn from(e: libtranslate::Error) -> Self {
+        Failure::Library(e)
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(e: std::io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+// Writes a line, flushed right away so that a reader of the pipe gets it
+fn print(line: impl std::fmt::Display) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", line)?;
+    stdout.flush()
+}
+
+// Writes one answer. In line mode a failed line still gets an empty line, so that output lines match input lines.
+fn answer(format: Format, text: &str, result: &libtranslate::Result<serde_json::Value>, field: &str) -> std::io::Result<()> {
+    match (format, result) {
+        (Format::Text, Ok(value)) => print(value[field].as_str().unwrap_or_default()),
+        (Format::Text, Err(e)) => {
+            eprintln!("error: {}", e);
+            print("")
+        },
+        (Format::Json, Ok(value)) => print(value),
+        (Format::Json, Err(e)) => print(json!({ "text": text, "error": e.to_string() })),
+    }
+}
+
+async fn translate(translator: &mut Translator, text: &str, languages: &Languages) -> libtranslate::Result<serde_json::Value> {
+    let translation = translator.translate(text, languages.from, languages.to).await?;
+    Ok(json!({
+        "text": text,
+        "result": translation.result(),
+        "source": translation.source().map(|source| source.to_string()),
+        "target": translation.target().to_string(),
+    }))
+}
+
+async fn detect(detector: &mut Detector, text: &str) -> libtranslate::Result<serde_json::Value> {
+    let language = detector.language(text).await?;
+    Ok(json!({ "text": text, "language": language.to_string() }))
+}
+
+async fn run(command: Command) -> Result<bool, Failure> {
+    let mut succeeded = true;
+    match command {
+        Command::Translate { text, languages, options } => {
+            let mut translator = options.services.translator()?;
+            if !options.lines {
+                let text = input(&text)?;
+                let result = translate(&mut translator, &text, &languages).await;
+                succeeded = result.is_ok();
+                answer(options.format, &text, &result, "result")?;
+            } else {
+                for line in std::io::stdin().lock().lines() {
+                    let line = line?;
+                    let result = translate(&mut translator, &line, &languages).await;
+                    succeeded &= result.is_ok();
+                    answer(options.format, &line, &result, "result")?;
+                }
+            }
+        },
+        Command::Detect { text, options } => {
+            let mut detector = options.services.detector()?;
+            if !options.lines {
+                let text = input(&text)?;
+                let result = detect(&mut detector, &text).await;
+                succeeded = result.is_ok();
+                answer(options.format, &text, &result, "language")?;
+            } else {
+                for line in std::io::stdin().lock().lines() {
+                    let line = line?;
+                    let result = detect(&mut detector, &line).await;
+                    succeeded &= result.is_ok();
+                    answer(options.format, &line, &result, "language")?;
+                }
+            }
+        },
+        Command::File { path, output, languages, services } => {
+            let mut translator = services.translator()?;
+            let text = std::fs::read_to_string(&path)?;
+            let translation = translator.translate(&text, languages.from, languages.to).await?;
+            match output {
+                Some(output) => std::fs::write(output, translation.result())?,
+                None => print(translation.result())?,
+            }
+        },
+        Command::ListLanguages { format } => {
+            for language in Language::all().iter().filter(|language| **language != Language::Auto) {
+                let name = language.metadata().map(|metadata| metadata.english_name()).unwrap_or_default();
+                match format {
+                    Format::Text => print(format!("{}\t{}", language, name))?,
+                    Format::Json => print(json!({ "code": language.to_string(), "name": name }))?,
+                }
+            }
+        },
+        Command::ListServices { format } => {
+            let detectors: Vec<String> = Detector::builder().build()?.services().iter().map(|service| service.name().to_string()).collect();
+            for service in Translator::builder().build()?.services() {
+                let detector = detectors.iter().any(|name| name == service.name());
+                match format {
+                    Format::Text => print(format!("{}{}", service.name(), if detector { "\t(detector)" } else { "" }))?,
+                    Format::Json => print(json!({ "name": service.name(), "detector": detector }))?,
+                }
+            }
+        },
+        #[cfg(feature = "server")]
+        Command::Serve { listen, cache_size, workers, services } => {
+            // Services that cannot detect are left to the translator
+            let detectors: Vec<String> = Detector::builder().build()?.services().iter().map(|service| service.name().to_string()).collect();
+            let detector = Services {
+                strategy: services.strategy.iter().filter(|name| detectors.contains(name)).cloned().collect(),
+                timeout: services.timeout,
+            };
+            let mut server = libtranslate::Server::new(services.translator()?, detector.detector()?).cache_size(cache_size);
+            for _ in 1..workers {
+                server = server.translator(services.translator()?).detector(detector.detector()?);
+            }
+            let listener = tokio::net::TcpListener::bind(&listen).await?;
+            eprintln!("listening on http://{}", listener.local_addr()?);
+            server.serve(listener).await?;
+        },
+    }
+    Ok(succeeded)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse().command).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // The reader of the pipe has all it wants, e.g. `| head`
+        Err(Failure::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Failure::Io(e)) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+        Err(Failure::Library(e)) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_arguments() {
+        use std::time::Duration;
+        use clap::Parser;
+        use libtranslate::{Language, Strategy};
+        use super::{Cli, Command, parse_seconds};
+
+        assert_eq!(parse_seconds("1.5").unwrap(), Duration::from_millis(1500));
+        assert!(parse_seconds("-1").is_err());
+        assert!(parse_seconds("soon").is_err());
+
+        let cli = Cli::try_parse_from(["libtranslate", "translate", "--to", "de", "-s", "a,b", "Hello", "world"]).unwrap();
+        let Command::Translate { text, languages, options } = cli.command else {
+            panic!("not a translate command");
+        };
+        assert_eq!(text, ["Hello", "world"]);
+        assert_eq!((languages.from, languages.to), (Language::Auto, Language::German));
+        assert!(matches!(options.services.strategy(), Strategy::Mix(names) if names == ["a", "b"]));
+
+        assert!(Cli::try_parse_from(["libtranslate", "translate", "--to", "xx"]).is_err());
+        assert!(Cli::try_parse_from(["libtranslate", "translate", "Hello"]).is_err());
+        // `--lines` reads standard input, so it takes no text
+        assert!(Cli::try_parse_from(["libtranslate", "translate", "--to", "de", "--lines", "Hello"]).is_err());
+        assert!(Cli::try_parse_from(["libtranslate", "detect", "--lines", "Hello"]).is_err());
+        assert!(Cli::try_parse_from(["libtranslate", "detect", "--lines"]).is_ok());
+        assert!(Cli::try_parse_from(["libtranslate", "file", "in.txt", "--to", "de", "--format", "json"]).is_err());
+        assert!(Cli::try_parse_from(["libtranslate", "file", "in.txt", "--to", "de", "--lines"]).is_err());
+        assert!(Cli::try_parse_from(["libtranslate", "file", "in.txt", "--to", "de", "-s", "a"]).is_ok());
+    }
+}
@@ -0,0 +1,318 @@
+//! Synchronous versions of `Translator`, `Detector`, `translate` and `language`, for code without an async runtime.
+//!
+//! Each `Translator` and `Detector` runs its requests on a runtime of its own, so they must not be used
+//! from within an async context, where tokio would panic.
+
+use crate::Result;
+use crate::Error;
+use crate::Language;
+use crate::Translation;
+use crate::Consensus;
+use crate::Capabilities;
+use crate::CancellationToken;
+use crate::{
+    BackoffPolicy,
+    Hedging,
+    Quota,
+    RateLimit,
+    Selection,
+    ServiceHealth,
+    Strategy,
+    TranslateOptions
+};
+use crate::api::{
+    HttpConfig,
+    Transport,
+    DetectorAPI,
+    TranslatorAPI
+};
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+fn runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| Error::IOError(e.to_string()))
+}
+
+/// A `crate::TranslatorBuilder` that builds a blocking `Translator`.
+pub struct TranslatorBuilder {
+    inner: crate::TranslatorBuilder,
+}
+
+impl TranslatorBuilder {
+    pub fn new() -> Self {
+        Self { inner: crate::TranslatorBuilder::new() }
+    }
+
+    pub fn build(self) -> Result<Translator> {
+        Translator::new(self.inner.build()?)
+    }
+
+    pub fn strategy(self, strategy: Strategy) -> Self {
+        Self { inner: self.inner.strategy(strategy) }
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { inner: self.inner.timeout(timeout) }
+    }
+
+    pub fn http(self, http: HttpConfig) -> Self {
+        Self { inner: self.inner.http(http) }
+    }
+
+    pub fn transport(self, transport: impl Transport + 'static) -> Self {
+        Self { inner: self.inner.transport(transport) }
+    }
+
+    pub fn backoff(self, backoff: BackoffPolicy) -> Self {
+        Self { inner: self.inner.backoff(backoff) }
+    }
+
+    pub fn rate_limit(self, name: &str, limit: RateLimit) -> Self {
+        Self { inner: self.inner.rate_limit(name, limit) }
+    }
+
+    pub fn max_queue_wait(self, max_queue_wait: Duration) -> Self {
+        Self { inner: self.inner.max_queue_wait(max_queue_wait) }
+    }
+
+    pub fn selection(self, selection: Selection) -> Self {
+        Self { inner: self.inner.selection(selection) }
+    }
+
+    pub fn load_state(self, path: impl AsRef<Path>) -> Self {
+        Self { inner: self.inner.load_state(path) }
+    }
+
+    pub fn hedging(self, hedging: Hedging) -> Self {
+        Self { inner: self.inner.hedging(hedging) }
+    }
+
+    pub fn cancellation(self, token: CancellationToken) -> Self {
+        Self { inner: self.inner.cancellation(token) }
+    }
+
+    pub fn service(self, name: &str, api: impl TranslatorAPI + 'static, weight: u64) -> Self {
+        Self { inner: self.inner.service(name, api, weight) }
+    }
+}
+
+/// A `crate::Translator` with synchronous methods.
+pub struct Translator {
+    inner: crate::Translator,
+    runtime: Runtime,
+}
+
+impl Translator {
+    pub fn builder() -> TranslatorBuilder {
+        TranslatorBuilder::new()
+    }
+
+    /// Wraps a translator built with the async builder.
+    pub fn new(translator: crate::Translator) -> Result<Self> {
+        Ok(Self { inner: translator, runtime: runtime()? })
+    }
+
+    pub fn translate(&mut self, text: &str, source: Language, target: Language) -> Result<Translation> {
+        self.runtime.block_on(self.inner.translate(text, source, target))
+    }
+
+    pub fn translate_with(&mut self, text: &str, source: Language, target: Language, options: &TranslateOptions) -> Result<Translation> {
+        self.runtime.block_on(self.inner.translate_with(text, source, target, options))
+    }
+
+    pub fn last_error(&self, api: &str) -> Option<Error> {
+        self.inner.last_error(api)
+    }
+
+    pub fn capabilities(&self) -> Vec<(String, Capabilities)> {
+        self.inner.capabilities()
+    }
+
+    pub fn quotas(&mut self) -> Vec<(String, Quota)> {
+        self.inner.quotas()
+    }
+
+    pub fn services(&mut self) -> Vec<ServiceHealth> {
+        self.inner.services()
+    }
+
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.inner.save_state(path)
+    }
+}
+
+/// A `crate::DetectorBuilder` that builds a blocking `Detector`.
+pub struct DetectorBuilder {
+    inner: crate::DetectorBuilder,
+}
+
+impl DetectorBuilder {
+    pub fn new() -> Self {
+        Self { inner: crate::DetectorBuilder::new() }
+    }
+
+    pub fn build(self) -> Result<Detector> {
+        Detector::new(self.inner.build()?)
+    }
+
+    pub fn strategy(self, strategy: Strategy) -> Self {
+        Self { inner: self.inner.strategy(strategy) }
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { inner: self.inner.timeout(timeout) }
+    }
+
+    pub fn http(self, http: HttpConfig) -> Self {
+        Self { inner: self.inner.http(http) }
+    }
+
+    pub fn transport(self, transport: impl Transport + 'static) -> Self {
+        Self { inner: self.inner.transport(transport) }
+    }
+
+    pub fn backoff(self, backoff: BackoffPolicy) -> Self {
+        Self { inner: self.inner.backoff(backoff) }
+    }
+
+    pub fn rate_limit(self, name: &str, limit: RateLimit) -> Self {
+        Self { inner: self.inner.rate_limit(name, limit) }
+    }
+
+    pub fn max_queue_wait(self, max_queue_wait: Duration) -> Self {
+        Self { inner: self.inner.max_queue_wait(max_queue_wait) }
+    }
+
+    pub fn selection(self, selection: Selection) -> Self {
+        Self { inner: self.inner.selection(selection) }
+    }
+
+    pub fn load_state(self, path: impl AsRef<Path>) -> Self {
+        Self { inner: self.inner.load_state(path) }
+    }
+
+    pub fn cancellation(self, token: CancellationToken) -> Self {
+        Self { inner: self.inner.cancellation(token) }
+    }
+
+    pub fn service(self, name: &str, api: impl DetectorAPI + 'static, weight: u64) -> Self {
+        Self { inner: self.inner.service(name, api, weight) }
+    }
+}
+
+/// A `crate::Detector` with synchronous methods.
+pub struct Detector {
+    inner: crate::Detector,
+    runtime: Runtime,
+}
+
+impl Detector {
+    pub fn builder() -> DetectorBuilder {
+        DetectorBuilder::new()
+    }
+
+    /// Wraps a detector built with the async builder.
+    pub fn new(detector: crate::Detector) -> Result<Self> {
+        Ok(Self { inner: detector, runtime: runtime()? })
+    }
+
+    pub fn language(&mut self, text: &str) -> Result<Language> {
+        self.runtime.block_on(self.inner.language(text))
+    }
+
+    pub fn consensus(&mut self, text: &str) -> Result<Consensus> {
+        self.runtime.block_on(self.inner.consensus(text))
+    }
+
+    pub fn quotas(&mut self) -> Vec<(String, Quota)> {
+        self.inner.quotas()
+    }
+
+    pub fn services(&mut self) -> Vec<ServiceHealth> {
+        self.inner.services()
+    }
+
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.inner.save_state(path)
+    }
+}
+
+thread_local! {
+    static DEFAULT_DETECTOR: RefCell<Option<Detector>> = const { RefCell::new(None) };
+    static DEFAULT_TRANSLATOR: RefCell<Option<Translator>> = const { RefCell::new(None) };
+}
+
+pub fn translate(text: &str, source: Language, target: Language) -> Result<Translation> {
+    DEFAULT_TRANSLATOR.with(|translator| {
+        let mut translator = translator.borrow_mut();
+        if translator.is_none() {
+            *translator = Some(Translator::builder().build()?);
+        }
+        translator.as_mut().unwrap().translate(text, source, target)
+    })
+}
+
+pub fn language(text: &str) -> Result<Language> {
+    DEFAULT_DETECTOR.with(|detector| {
+        let mut detector = detector.borrow_mut();
+        if detector.is_none() {
+            *detector = Some(Detector::builder().build()?);
+        }
+        detector.as_mut().unwrap().language(text)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_blocking() {
+        use crate::{Error, Language, Result, Strategy, Translation};
+        use crate::api::{DetectorAPI, Request, TranslatorAPI};
+        use super::{Detector, Translator};
+        use async_trait::async_trait;
+        use std::time::Duration;
+
+        // Answers after a timer, which needs the runtime of the blocking translator
+        struct Slow;
+
+        #[async_trait]
+        impl TranslatorAPI for Slow {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+        }
+
+        struct French;
+
+        #[async_trait]
+        impl DetectorAPI for French {
+            async fn language(&self, _request: &Request, _text: &str) -> Result<Language> {
+                Ok(Language::French)
+            }
+        }
+
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("slow".to_string()))
+            .service("slow", Slow, 100)
+            .build()
+            .unwrap();
+        assert_eq!(translator.translate("hello", Language::Auto, Language::German).unwrap().result(), "HELLO");
+        assert!(matches!(translator.translate("hello", Language::German, Language::German), Err(Error::TargetEqualToSource)));
+        assert_eq!(translator.services().len(), 1);
+
+        let mut detector = Detector::builder()
+            .strategy(Strategy::Single("french".to_string()))
+            .service("french", French, 100)
+            .build()
+            .unwrap();
+        assert_eq!(detector.language("bonjour").unwrap(), Language::French);
+        assert_eq!(detector.consensus("bonjour").unwrap().language(), Language::French);
+    }
+}
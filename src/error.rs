@@ -1,6 +1,7 @@
-use thiserror;
-
-#[derive(thiserror::Error, Debug, Clone)]
+/// `NoAvailableService`, `DeadlineExceeded` and `Cancelled` carry the requests sent during the call.
+/// The other errors carry none, even when requests were sent before them: a `RateLimited` from waiting
+/// for a rate limit, or a `LengthLimit` for a text that cannot be split any further.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
 pub enum Error {
     #[error("the detector's strategy is not specified")]
     NoDetectorRegistrationService,
@@ -11,6 +12,15 @@ pub enum Error {
     #[error("invalid service name")]
     InvalidServiceName,
 
+    #[error("invalid language code `{0}`")]
+    InvalidLanguage(String),
+
+    #[error("the language `{0}` is not supported")]
+    UnsupportedLanguage(String),
+
+    #[error("translating from `{0}` to `{1}` is not supported")]
+    UnsupportedDirection(crate::Language, crate::Language),
+
     #[error("no target language set")]
     NoTargetLanguage,
 
@@ -21,7 +31,26 @@ pub enum Error {
     TargetEqualToSource,
 
     #[error("No service available, please try again later")]
-    NoAvailableService(std::time::Duration),
+    NoAvailableService {
+        /// When the first blocked service is available again.
+        retry_after: std::time::Duration,
+        /// The requests sent during the call, in order.
+        attempts: Vec<crate::Attempt>,
+        /// The last error of every service that has one.
+        last_errors: Vec<(String, Error)>,
+    },
+
+    #[error("the deadline of the call was exceeded")]
+    DeadlineExceeded {
+        /// The requests sent during the call, in order.
+        attempts: Vec<crate::Attempt>,
+    },
+
+    #[error("the call was cancelled")]
+    Cancelled {
+        /// The requests sent during the call, in order.
+        attempts: Vec<crate::Attempt>,
+    },
 
     #[error("Input text is too long {0}")]
     LengthLimit(usize),
@@ -35,14 +64,90 @@ pub enum Error {
     #[error("{0}")]
     ReqwestError(String),
 
+    #[error("invalid HTTP configuration: {0}")]
+    InvalidHttpConfig(String),
+
     #[error("HTML parsing error {0}")]
     HTMLParsingError(String),
 
     #[error("JSON parsing error {0}")]
     JSONParsingError(String),
 
+    #[error("I/O error {0}")]
+    IOError(String),
+
+    #[error("no recorded response for {0}")]
+    NotRecorded(String),
+
     #[error("{0}")]
     Status(String),
+
+    #[error("too many requests, retry after {0:?}")]
+    RateLimited(Option<std::time::Duration>),
+
+    #[error("authentication failed: {0}")]
+    AuthFailure(String),
+
+    #[error("quota exhausted: {0}")]
+    QuotaExhausted(String),
+
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+}
+
+impl Error {
+    /// Maps an HTTP error status to an error, `retry_after` is the value of the `Retry-After` header.
+    /// The languages of a 501 are not known here, they stay `Auto` until the translator fills them in.
+    pub(crate) fn from_status(status: u16, reason: &str, retry_after: Option<&str>) -> Error {
+        let text = format!("{} {}", status, reason);
+        match status {
+            429 => Error::RateLimited(retry_after.and_then(parse_retry_after)),
+            401 | 403 => Error::AuthFailure(text),
+            402 | 456 => Error::QuotaExhausted(text),
+            413 | 414 => Error::PayloadTooLarge(text),
+            501 => Error::UnsupportedDirection(crate::Language::Auto, crate::Language::Auto),
+            _ => Error::Status(text),
+        }
+    }
+
+    // Puts `attempts` before those of an error that carries attempts, other errors are returned as is
+    pub(crate) fn prepend_attempts(mut self, mut attempts: Vec<crate::Attempt>) -> Error {
+        if let Error::NoAvailableService { attempts: own, .. } | Error::DeadlineExceeded { attempts: own } | Error::Cancelled { attempts: own } = &mut self {
+            attempts.append(own);
+            *own = attempts;
+        }
+        self
+    }
+
+    // The leading status code of `Status`, e.g. `501` for `501 Not Implemented`
+    pub(crate) fn status_code(&self) -> Option<u16> {
+        let Error::Status(status) = self else {
+            return None;
+        };
+        status.split(' ').next()?.parse().ok()
+    }
+
+    /// Whether sending the same request again later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited(_) | Error::ReqwestError(_) | Error::NoAvailableService { .. } | Error::DeadlineExceeded { .. } => true,
+            Error::Status(_) => self.status_code().is_none_or(|code| code == 408 || code >= 500),
+            _ => false,
+        }
+    }
+}
+
+// Longer delays are cut to this, so that no header can push a service out of reach for good
+const MAX_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(86_400);
+
+// `Retry-After` is either a number of seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(secs).min(MAX_RETRY_AFTER));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(std::time::SystemTime::now()).unwrap_or_default().min(MAX_RETRY_AFTER))
 }
 
 pub(crate) mod constant {
@@ -56,5 +161,25 @@ pub type Result<T> = std::result::Result<T, Error>;
 mod tests {
     #[test]
     fn test_error_type() {
+        use std::time::Duration;
+        use crate::Language;
+        use super::Error;
+
+        assert!(matches!(Error::from_status(429, "Too Many Requests", Some("120")), Error::RateLimited(Some(d)) if d == Duration::from_secs(120)));
+        assert!(matches!(Error::from_status(429, "Too Many Requests", Some("soon")), Error::RateLimited(None)));
+        assert!(matches!(Error::from_status(429, "Too Many Requests", Some("Wed, 21 Oct 2015 07:28:00 GMT")), Error::RateLimited(Some(d)) if d.is_zero()));
+        assert!(matches!(Error::from_status(429, "Too Many Requests", Some("18446744073709551615")), Error::RateLimited(Some(d)) if d == Duration::from_secs(86_400)));
+        assert!(matches!(Error::from_status(401, "Unauthorized", None), Error::AuthFailure(_)));
+        assert!(matches!(Error::from_status(456, "Quota Exceeded", None), Error::QuotaExhausted(_)));
+        assert!(matches!(Error::from_status(413, "Payload Too Large", None), Error::PayloadTooLarge(_)));
+        assert_eq!(Error::from_status(501, "Not Implemented", None), Error::UnsupportedDirection(Language::Auto, Language::Auto));
+        assert_eq!(Error::from_status(500, "Internal Server Error", None).status_code(), Some(500));
+
+        assert!(Error::RateLimited(None).is_retryable());
+        assert!(Error::from_status(503, "Service Unavailable", None).is_retryable());
+        assert!(!Error::from_status(404, "Not Found", None).is_retryable());
+        assert!(!Error::from_status(402, "Payment Required", None).is_retryable());
+        assert!(!Error::PayloadTooLarge(String::new()).is_retryable());
+        assert!(!Error::ReturnedEmptyResult.is_retryable());
     }
 }
\ No newline at end of file
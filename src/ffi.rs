@@ -0,0 +1,393 @@
+//! A C API over the blocking `Translator` and `Detector`, see `include/libtranslate.h`.
+//!
+//! Every function returns an `LtStatus`, the message of the last error of the calling thread is given by `lt_last_error`.
+//! Strings returned through out-parameters are owned by the caller and freed with `lt_string_free`.
+//! A handle must not be used by two threads at once.
+
+use crate::Error;
+use crate::Language;
+use crate::Strategy;
+use crate::blocking::{
+    Detector,
+    Translator
+};
+
+use std::cell::RefCell;
+use std::ffi::{
+    c_char,
+    CStr,
+    CString
+};
+use std::panic::{
+    catch_unwind,
+    AssertUnwindSafe
+};
+use std::ptr;
+use std::time::Duration;
+
+/// The result of a call, mapped from `Error`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LtStatus {
+    Ok = 0,
+    /// A pointer was null, or a string was not valid UTF-8.
+    InvalidArgument = 1,
+    InvalidLanguage = 2,
+    UnsupportedLanguage = 3,
+    InvalidService = 4,
+    NoAvailableService = 5,
+    RateLimited = 6,
+    DeadlineExceeded = 7,
+    AuthFailure = 8,
+    QuotaExhausted = 9,
+    LengthLimit = 10,
+    Network = 11,
+    Parse = 12,
+    Io = 13,
+    /// The service answered with an unexpected HTTP status.
+    Status = 14,
+    /// Any other error, see `lt_last_error`.
+    Other = 15,
+    /// A bug in libtranslate, the handle should not be used anymore.
+    Panic = 16,
+}
+
+impl From<&Error> for LtStatus {
+    fn from(e: &Error) -> Self {
+        match e {
+            Error::InvalidLanguage(_) | Error::TargetLanguageIsAuto | Error::TargetEqualToSource | Error::NoTargetLanguage => LtStatus::InvalidLanguage,
+            Error::UnsupportedLanguage(_) | Error::UnsupportedDirection(..) => LtStatus::UnsupportedLanguage,
+            Error::InvalidServiceName | Error::NoDetectorRegistrationService | Error::NoTranslatorRegistrationService => LtStatus::InvalidService,
+            Error::NoAvailableService { .. } => LtStatus::NoAvailableService,
+            Error::RateLimited(_) => LtStatus::RateLimited,
+            Error::DeadlineExceeded { .. } => LtStatus::DeadlineExceeded,
+            Error::AuthFailure(_) => LtStatus::AuthFailure,
+            Error::QuotaExhausted(_) => LtStatus::QuotaExhausted,
+            Error::LengthLimit(_) | Error::PayloadTooLarge(_) => LtStatus::LengthLimit,
+            Error::ReqwestError(_) => LtStatus::Network,
+            Error::HTMLParsingError(_) | Error::JSONParsingError(_) | Error::UnexpectedResult(_) | Error::ReturnedEmptyResult => LtStatus::Parse,
+            Error::IOError(_) => LtStatus::Io,
+            Error::Status(_) => LtStatus::Status,
+            _ => LtStatus::Other,
+        }
+    }
+}
+
+/// A translator, created by `lt_translator_new` and destroyed by `lt_translator_free`.
+pub struct LtTranslator(Translator);
+
+/// A detector, created by `lt_detector_new` and destroyed by `lt_detector_free`.
+pub struct LtDetector(Detector);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail(status: LtStatus, message: &str) -> LtStatus {
+    // A message with a NUL byte is cut there
+    let message = message.split('\0').next().unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+    status
+}
+
+// Runs `f`, recording its error and turning a panic into `LtStatus::Panic`
+fn call(f: impl FnOnce() -> std::result::Result<(), (LtStatus, String)>) -> LtStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => LtStatus::Ok,
+        Ok(Err((status, message))) => fail(status, &message),
+        Err(_) => fail(LtStatus::Panic, "libtranslate panicked"),
+    }
+}
+
+fn library(e: Error) -> (LtStatus, String) {
+    (LtStatus::from(&e), e.to_string())
+}
+
+fn invalid(message: &str) -> (LtStatus, String) {
+    (LtStatus::InvalidArgument, message.to_string())
+}
+
+// A borrowed C string, `None` for a null pointer
+unsafe fn text<'a>(text: *const c_char, name: &str) -> std::result::Result<Option<&'a str>, (LtStatus, String)> {
+    if text.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(text).to_str().map(Some).map_err(|_| invalid(&format!("`{}` is not valid UTF-8", name)))
+}
+
+unsafe fn required<'a>(value: *const c_char, name: &str) -> std::result::Result<&'a str, (LtStatus, String)> {
+    text(value, name)?.ok_or_else(|| invalid(&format!("`{}` is null", name)))
+}
+
+// Comma-separated service names, all of them if empty
+fn strategy(services: Option<&str>) -> Strategy {
+    let names: Vec<String> = services.unwrap_or_default().split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    match names.len() {
+        0 => Strategy::Default,
+        1 => Strategy::Single(names[0].clone()),
+        _ => Strategy::Mix(names),
+    }
+}
+
+fn language(code: &str) -> std::result::Result<Language, (LtStatus, String)> {
+    code.parse().map_err(library)
+}
+
+// The default timeout if not positive, an error if too large for a `Duration`
+fn timeout(seconds: f64) -> std::result::Result<Option<Duration>, (LtStatus, String)> {
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(seconds).map(Some).map_err(|_| invalid(&format!("`timeout_seconds` {} is too large", seconds)))
+}
+
+fn output(value: &str, out: *mut *mut c_char) -> std::result::Result<(), (LtStatus, String)> {
+    let value = CString::new(value).map_err(|_| (LtStatus::Parse, "the answer contains a NUL byte".to_string()))?;
+    unsafe { *out = value.into_raw() };
+    Ok(())
+}
+
+/// Creates a translator using `services`, comma-separated names in priority order, or all of them if null or empty.
+/// `timeout_seconds` is the timeout of each request, the default one if not positive, and an invalid argument if infinite.
+///
+/// # Safety
+/// `services` is null or a NUL-terminated string, `translator` is a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn lt_translator_new(services: *const c_char, timeout_seconds: f64, translator: *mut *mut LtTranslator) -> LtStatus {
+    call(|| {
+        if translator.is_null() {
+            return Err(invalid("`translator` is null"));
+        }
+        let mut builder = Translator::builder().strategy(strategy(text(services, "services")?));
+        if let Some(timeout) = timeout(timeout_seconds)? {
+            builder = builder.timeout(timeout);
+        }
+        let created = builder.build().map_err(library)?;
+        *translator = Box::into_raw(Box::new(LtTranslator(created)));
+        Ok(())
+    })
+}
+
+/// Destroys a translator, null is ignored.
+///
+/// # Safety
+/// `translator` is null or was returned by `lt_translator_new`, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lt_translator_free(translator: *mut LtTranslator) {
+    if !translator.is_null() {
+        drop(Box::from_raw(translator));
+    }
+}
+
+/// Translates `text` from `source` to `target`, language codes such as `en`, `source` may be `auto`.
+/// On success `result` is set to the translation, to be freed with `lt_string_free`.
+///
+/// # Safety
+/// `translator` was returned by `lt_translator_new`, the strings are NUL-terminated, `result` is a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn lt_translate(
+    translator: *mut LtTranslator,
+    text: *const c_char,
+    source: *const c_char,
+    target: *const c_char,
+    result: *mut *mut c_char
+) -> LtStatus {
+    call(|| {
+        if translator.is_null() || result.is_null() {
+            return Err(invalid("`translator` or `result` is null"));
+        }
+        let input = required(text, "text")?;
+        let source = language(required(source, "source")?)?;
+        let target = language(required(target, "target")?)?;
+        let translation = (*translator).0.translate(input, source, target).map_err(library)?;
+        output(translation.result(), result)
+    })
+}
+
+/// Creates a detector using `services`, like `lt_translator_new`.
+///
+/// # Safety
+/// `services` is null or a NUL-terminated string, `detector` is a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn lt_detector_new(services: *const c_char, timeout_seconds: f64, detector: *mut *mut LtDetector) -> LtStatus {
+    call(|| {
+        if detector.is_null() {
+            return Err(invalid("`detector` is null"));
+        }
+        let mut builder = Detector::builder().strategy(strategy(text(services, "services")?));
+        if let Some(timeout) = timeout(timeout_seconds)? {
+            builder = builder.timeout(timeout);
+        }
+        let created = builder.build().map_err(library)?;
+        *detector = Box::into_raw(Box::new(LtDetector(created)));
+        Ok(())
+    })
+}
+
+/// Destroys a detector, null is ignored.
+///
+/// # Safety
+/// `detector` is null or was returned by `lt_detector_new`, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lt_detector_free(detector: *mut LtDetector) {
+    if !detector.is_null() {
+        drop(Box::from_raw(detector));
+    }
+}
+
+/// Detects the language of `text`. On success `language` is set to its code, to be freed with `lt_string_free`.
+///
+/// # Safety
+/// `detector` was returned by `lt_detector_new`, `text` is NUL-terminated, `language` is a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn lt_detect(detector: *mut LtDetector, text: *const c_char, language: *mut *mut c_char) -> LtStatus {
+    call(|| {
+        if detector.is_null() || language.is_null() {
+            return Err(invalid("`detector` or `language` is null"));
+        }
+        let input = required(text, "text")?;
+        let detected = (*detector).0.language(input).map_err(library)?;
+        output(&detected.to_string(), language)
+    })
+}
+
+/// The message of the last error of the calling thread, or null. To be freed with `lt_string_free`.
+#[no_mangle]
+pub extern "C" fn lt_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| last.borrow().clone().map_or(ptr::null_mut(), CString::into_raw))
+}
+
+/// Frees a string returned by libtranslate, null is ignored.
+///
+/// # Safety
+/// `string` is null or was returned by libtranslate, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lt_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// The version of libtranslate, a static string that must not be freed.
+#[no_mangle]
+pub extern "C" fn lt_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_ffi() {
+        use crate::{Language, Result, Strategy, Translation};
+        use crate::api::{Request, TranslatorAPI};
+        use crate::blocking::Translator;
+        use super::*;
+        use async_trait::async_trait;
+        use std::ffi::{CStr, CString};
+
+        struct Upper;
+
+        #[async_trait]
+        impl TranslatorAPI for Upper {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+        }
+
+        let translator = Translator::builder()
+            .strategy(Strategy::Single("upper".to_string()))
+            .service("upper", Upper, 100)
+            .build()
+            .unwrap();
+        let translator = Box::into_raw(Box::new(LtTranslator(translator)));
+        let string = |value: &str| CString::new(value).unwrap();
+        let last_error = || unsafe {
+            let message = lt_last_error();
+            let text = CStr::from_ptr(message).to_str().unwrap().to_string();
+            lt_string_free(message);
+            text
+        };
+
+        unsafe {
+            let mut result = std::ptr::null_mut();
+            let status = lt_translate(translator, string("grüße").as_ptr(), string("auto").as_ptr(), string("en").as_ptr(), &mut result);
+            assert_eq!(status, LtStatus::Ok);
+            assert_eq!(CStr::from_ptr(result).to_str().unwrap(), "GRÜSSE");
+            lt_string_free(result);
+
+            let status = lt_translate(translator, string("hello").as_ptr(), string("auto").as_ptr(), string("xx").as_ptr(), &mut result);
+            assert_eq!(status, LtStatus::InvalidLanguage);
+            assert_eq!(last_error(), "invalid language code `xx`");
+
+            let status = lt_translate(translator, std::ptr::null(), string("auto").as_ptr(), string("en").as_ptr(), &mut result);
+            assert_eq!(status, LtStatus::InvalidArgument);
+            lt_translator_free(translator);
+
+            let mut created = std::ptr::null_mut();
+            assert_eq!(lt_translator_new(string("missing").as_ptr(), 0.0, &mut created), LtStatus::InvalidService);
+            assert!(created.is_null());
+            assert_eq!(lt_translator_new(std::ptr::null(), f64::INFINITY, &mut created), LtStatus::InvalidArgument);
+            assert!(created.is_null());
+            let mut detector = std::ptr::null_mut();
+            assert_eq!(lt_detector_new(std::ptr::null(), 5.0, &mut detector), LtStatus::Ok);
+            lt_detector_free(detector);
+            let mut detector = std::ptr::null_mut();
+            assert_eq!(lt_detector_new(string("missing").as_ptr(), 0.0, &mut detector), LtStatus::InvalidService);
+            assert_eq!(lt_detector_new(std::ptr::null(), 1e300, &mut detector), LtStatus::InvalidArgument);
+            assert_eq!(lt_detector_new(std::ptr::null(), 0.0, std::ptr::null_mut()), LtStatus::InvalidArgument);
+            assert!(detector.is_null());
+
+            assert_eq!(CStr::from_ptr(lt_version()).to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        }
+    }
+
+    #[test]
+    fn test_ffi_detect() {
+        use crate::{Error, Language, Result, Strategy};
+        use crate::api::{DetectorAPI, Request};
+        use crate::blocking::Detector;
+        use super::*;
+        use async_trait::async_trait;
+        use std::ffi::{CStr, CString};
+
+        // Detects French, and fails on empty text
+        struct French;
+
+        #[async_trait]
+        impl DetectorAPI for French {
+            async fn language(&self, _request: &Request, text: &str) -> Result<Language> {
+                match text.is_empty() {
+                    true => Err(Error::ReturnedEmptyResult),
+                    false => Ok(Language::French),
+                }
+            }
+        }
+
+        let detector = Detector::builder()
+            .strategy(Strategy::Single("french".to_string()))
+            .service("french", French, 100)
+            .build()
+            .unwrap();
+        let detector = Box::into_raw(Box::new(LtDetector(detector)));
+        let string = |value: &str| CString::new(value).unwrap();
+
+        unsafe {
+            let mut language = std::ptr::null_mut();
+            assert_eq!(lt_detect(detector, string("bonjour").as_ptr(), &mut language), LtStatus::Ok);
+            assert_eq!(CStr::from_ptr(language).to_str().unwrap(), "fr");
+            lt_string_free(language);
+
+            let mut language = std::ptr::null_mut();
+            assert_eq!(lt_detect(detector, string("").as_ptr(), &mut language), LtStatus::NoAvailableService);
+            assert!(language.is_null());
+            assert_eq!(lt_detect(detector, std::ptr::null(), &mut language), LtStatus::InvalidArgument);
+            assert_eq!(lt_detect(detector, string("bonjour").as_ptr(), std::ptr::null_mut()), LtStatus::InvalidArgument);
+            let invalid = [0xffu8, 0];
+            assert_eq!(lt_detect(detector, invalid.as_ptr() as *const c_char, &mut language), LtStatus::InvalidArgument);
+            lt_detector_free(detector);
+        }
+    }
+}
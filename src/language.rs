@@ -1,4 +1,8 @@
-#[derive(PartialEq, Debug, Clone, Copy)]
+use crate::Error;
+use crate::Result;
+use crate::LanguageTag;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 #[allow(non_camel_case_types)]
 pub enum Language {
     Auto                 , // auto
@@ -106,7 +110,30 @@ pub enum Language {
     Zulu                 , // zu
 }
 
+// Every variant, in declaration order
+const ALL: &[Language] = {
+    use self::Language::*;
+
+    &[
+        Auto, Afrikaans, Amharic, Arabic, Azerbaijani, Belarusian, Bulgarian, Bengali, Bosnian, Catalan, Corsican,
+        Czech, Welsh, Danish, German, Greek, English, Esperanto, Spanish, Estonian, Basque, Persian, Finnish,
+        French, WesternFrisian, Irish, ScottishGaelic, Galician, Gujarati, Hausa, Hebrew, Hindi, Croatian,
+        HaitianCreole, Hungarian, Armenian, Indonesian, Igbo, Icelandic, Italian, Japanese, Georgian, Kazakh,
+        CentralKhmer, Kannada, Korean, Kurdish, Kirghiz, Latin, Luxembourgish, Lao, Lithuanian, Latvian, Malagasy,
+        Maori, Macedonian, Malayalam, Mongolian, Marathi, Malay, Maltese, Burmese, Nepali, Dutch, Norwegian,
+        Chichewa, Oriya, Panjabi, Polish, Pushto, Portuguese, Romanian, Russian, Sindhi, Sinhalese, Slovak,
+        Slovenian, Samoan, Shona, Somali, Albanian, Serbian, SothoSouthern, Sundanese, Swedish, Swahili, Tamil,
+        Telugu, Tajik, Thai, Tagalog, Turkish, Uighur, Ukrainian, Urdu, Uzbek, Vietnamese, Xhosa, Yiddish, Yoruba,
+        SimpleChinese, TraditionalChinese, Zulu,
+    ]
+};
+
 impl Language {
+    /// Every language, starting with `Language::Auto`.
+    pub fn all() -> &'static [Language] {
+        ALL
+    }
+
     pub(crate) fn abbreviation(&self) -> Option<&'static str> {
         use self::Language::*;
 
@@ -327,4 +354,69 @@ impl Language {
             _        => None,
         }
     }
-}
\ No newline at end of file
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.abbreviation().unwrap())
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = Error;
+
+    // Accepts the codes produced by `Display` as well as any BCP-47 tag with a close enough `Language`
+    fn from_str(s: &str) -> Result<Self> {
+        Language::from(s)
+            .or_else(|| LanguageTag::parse(s).ok()?.to_language())
+            .ok_or_else(|| Error::InvalidLanguage(s.to_string()))
+    }
+}
+
+// Serialized as the language code, e.g. `"zh-CN"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Language {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.abbreviation().unwrap())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Language {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_string_round_trip() {
+        use crate::Error;
+        use crate::Language;
+
+        for code in ["auto", "af", "en", "he", "zh-CN", "zh-TW", "zu"] {
+            assert_eq!(code.parse::<Language>().unwrap().to_string(), code);
+        }
+        assert_eq!(Language::SimpleChinese.to_string(), "zh-CN");
+        assert_eq!("zh-Hans".parse::<Language>().unwrap(), Language::SimpleChinese);
+        assert_eq!("pt-BR".parse::<Language>().unwrap(), Language::Portuguese);
+        assert!(matches!("xx".parse::<Language>(), Err(Error::InvalidLanguage(_))));
+
+        assert_eq!(Language::all().len(), 103);
+        for language in Language::all() {
+            assert_eq!(language.to_string().parse::<Language>().unwrap(), *language);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use crate::Language;
+
+        assert_eq!(serde_json::to_string(&Language::TraditionalChinese).unwrap(), "\"zh-TW\"");
+        assert_eq!(serde_json::from_str::<Language>("\"de\"").unwrap(), Language::German);
+        assert!(serde_json::from_str::<Language>("\"German\"").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::Language;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// CLDR cardinal plural categories, see https://cldr.unicode.org/index/cldr-spec/plural-rules
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Static information about a `Language`, returned by `Language::metadata`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LanguageMetadata {
+    english_name: &'static str,
+    native_name: &'static str,
+    iso639_1: &'static str,
+    iso639_2: &'static str,
+    iso639_3: &'static str,
+    script: &'static str,
+    direction: Direction,
+    plural_categories: &'static [PluralCategory],
+}
+
+impl LanguageMetadata {
+    pub fn english_name(&self) -> &'static str {
+        self.english_name
+    }
+
+    /// The name of the language in the language itself.
+    pub fn native_name(&self) -> &'static str {
+        self.native_name
+    }
+
+    pub fn iso639_1(&self) -> &'static str {
+        self.iso639_1
+    }
+
+    /// The ISO 639-2/T (terminology) code.
+    pub fn iso639_2(&self) -> &'static str {
+        self.iso639_2
+    }
+
+    /// The ISO 639-3 code, the macrolanguage code where the language is one.
+    pub fn iso639_3(&self) -> &'static str {
+        self.iso639_3
+    }
+
+    /// The ISO 15924 code of the primary script, e.g. `Latn`.
+    pub fn script(&self) -> &'static str {
+        self.script
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_rtl(&self) -> bool {
+        self.direction == Direction::RightToLeft
+    }
+
+    /// Languages without CLDR plural rules use the root rules, which only have `Other`.
+    pub fn plural_categories(&self) -> &'static [PluralCategory] {
+        self.plural_categories
+    }
+}
+
+use self::PluralCategory::*;
+
+const OTHER: &[PluralCategory] = &[Other];
+const ONE_OTHER: &[PluralCategory] = &[One, Other];
+const ONE_MANY_OTHER: &[PluralCategory] = &[One, Many, Other];
+const ONE_FEW_OTHER: &[PluralCategory] = &[One, Few, Other];
+const ONE_FEW_MANY_OTHER: &[PluralCategory] = &[One, Few, Many, Other];
+const ONE_TWO_OTHER: &[PluralCategory] = &[One, Two, Other];
+const ONE_TWO_FEW_OTHER: &[PluralCategory] = &[One, Two, Few, Other];
+const ONE_TWO_FEW_MANY_OTHER: &[PluralCategory] = &[One, Two, Few, Many, Other];
+const ZERO_ONE_OTHER: &[PluralCategory] = &[Zero, One, Other];
+const ALL: &[PluralCategory] = &[Zero, One, Two, Few, Many, Other];
+
+const LTR: Direction = Direction::LeftToRight;
+const RTL: Direction = Direction::RightToLeft;
+
+#[allow(clippy::too_many_arguments)]
+const fn meta(
+    english_name: &'static str,
+    native_name: &'static str,
+    iso639_1: &'static str,
+    iso639_2: &'static str,
+    iso639_3: &'static str,
+    script: &'static str,
+    direction: Direction,
+    plural_categories: &'static [PluralCategory]
+) -> LanguageMetadata {
+    LanguageMetadata { english_name, native_name, iso639_1, iso639_2, iso639_3, script, direction, plural_categories }
+}
+
+impl Language {
+    /// Names, codes, script, text direction and plural categories. `None` for `Language::Auto`.
+    pub fn metadata(&self) -> Option<LanguageMetadata> {
+        use crate::Language::*;
+
+        let metadata = match self {
+            Auto                 => return None,
+            Afrikaans            => meta("Afrikaans",             "Afrikaans",        "af", "afr", "afr", "Latn", LTR, ONE_OTHER),
+            Amharic              => meta("Amharic",               "አማርኛ",             "am", "amh", "amh", "Ethi", LTR, ONE_OTHER),
+            Arabic               => meta("Arabic",                "العربية",          "ar", "ara", "ara", "Arab", RTL, ALL),
+            Azerbaijani          => meta("Azerbaijani",           "azərbaycan",       "az", "aze", "aze", "Latn", LTR, ONE_OTHER),
+            Belarusian           => meta("Belarusian",            "беларуская",       "be", "bel", "bel", "Cyrl", LTR, ONE_FEW_MANY_OTHER),
+            Bulgarian            => meta("Bulgarian",             "български",        "bg", "bul", "bul", "Cyrl", LTR, ONE_OTHER),
+            Bengali              => meta("Bengali",               "বাংলা",             "bn", "ben", "ben", "Beng", LTR, ONE_OTHER),
+            Bosnian              => meta("Bosnian",               "bosanski",         "bs", "bos", "bos", "Latn", LTR, ONE_FEW_OTHER),
+            Catalan              => meta("Catalan",               "català",           "ca", "cat", "cat", "Latn", LTR, ONE_MANY_OTHER),
+            Corsican             => meta("Corsican",              "corsu",            "co", "cos", "cos", "Latn", LTR, OTHER),
+            Czech                => meta("Czech",                 "čeština",          "cs", "ces", "ces", "Latn", LTR, ONE_FEW_MANY_OTHER),
+            Welsh                => meta("Welsh",                 "Cymraeg",          "cy", "cym", "cym", "Latn", LTR, ALL),
+            Danish               => meta("Danish",                "dansk",            "da", "dan", "dan", "Latn", LTR, ONE_OTHER),
+            German               => meta("German",                "Deutsch",          "de", "deu", "deu", "Latn", LTR, ONE_OTHER),
+            Greek                => meta("Greek",                 "Ελληνικά",         "el", "ell", "ell", "Grek", LTR, ONE_OTHER),
+            English              => meta("English",               "English",          "en", "eng", "eng", "Latn", LTR, ONE_OTHER),
+            Esperanto            => meta("Esperanto",             "Esperanto",        "eo", "epo", "epo", "Latn", LTR, ONE_OTHER),
+            Spanish              => meta("Spanish",               "español",          "es", "spa", "spa", "Latn", LTR, ONE_MANY_OTHER),
+            Estonian             => meta("Estonian",              "eesti",            "et", "est", "est", "Latn", LTR, ONE_OTHER),
+            Basque               => meta("Basque",                "euskara",          "eu", "eus", "eus", "Latn", LTR, ONE_OTHER),
+            Persian              => meta("Persian",               "فارسی",            "fa", "fas", "fas", "Arab", RTL, ONE_OTHER),
+            Finnish              => meta("Finnish",               "suomi",            "fi", "fin", "fin", "Latn", LTR, ONE_OTHER),
+            French               => meta("French",                "français",         "fr", "fra", "fra", "Latn", LTR, ONE_MANY_OTHER),
+            WesternFrisian       => meta("Western Frisian",       "Frysk",            "fy", "fry", "fry", "Latn", LTR, ONE_OTHER),
+            Irish                => meta("Irish",                 "Gaeilge",          "ga", "gle", "gle", "Latn", LTR, ONE_TWO_FEW_MANY_OTHER),
+            ScottishGaelic       => meta("Scottish Gaelic",       "Gàidhlig",         "gd", "gla", "gla", "Latn", LTR, ONE_TWO_FEW_OTHER),
+            Galician             => meta("Galician",              "galego",           "gl", "glg", "glg", "Latn", LTR, ONE_OTHER),
+            Gujarati             => meta("Gujarati",              "ગુજરાતી",           "gu", "guj", "guj", "Gujr", LTR, ONE_OTHER),
+            Hausa                => meta("Hausa",                 "Hausa",            "ha", "hau", "hau", "Latn", LTR, ONE_OTHER),
+            Hebrew               => meta("Hebrew",                "עברית",            "he", "heb", "heb", "Hebr", RTL, ONE_TWO_OTHER),
+            Hindi                => meta("Hindi",                 "हिन्दी",             "hi", "hin", "hin", "Deva", LTR, ONE_OTHER),
+            Croatian             => meta("Croatian",              "hrvatski",         "hr", "hrv", "hrv", "Latn", LTR, ONE_FEW_OTHER),
+            HaitianCreole        => meta("Haitian Creole",        "Kreyòl ayisyen",   "ht", "hat", "hat", "Latn", LTR, OTHER),
+            Hungarian            => meta("Hungarian",             "magyar",           "hu", "hun", "hun", "Latn", LTR, ONE_OTHER),
+            Armenian             => meta("Armenian",              "հայերեն",          "hy", "hye", "hye", "Armn", LTR, ONE_OTHER),
+            Indonesian           => meta("Indonesian",            "Indonesia",        "id", "ind", "ind", "Latn", LTR, OTHER),
+            Igbo                 => meta("Igbo",                  "Igbo",             "ig", "ibo", "ibo", "Latn", LTR, OTHER),
+            Icelandic            => meta("Icelandic",             "íslenska",         "is", "isl", "isl", "Latn", LTR, ONE_OTHER),
+            Italian              => meta("Italian",               "italiano",         "it", "ita", "ita", "Latn", LTR, ONE_MANY_OTHER),
+            Japanese             => meta("Japanese",              "日本語",            "ja", "jpn", "jpn", "Jpan", LTR, OTHER),
+            Georgian             => meta("Georgian",              "ქართული",          "ka", "kat", "kat", "Geor", LTR, ONE_OTHER),
+            Kazakh               => meta("Kazakh",                "қазақ тілі",       "kk", "kaz", "kaz", "Cyrl", LTR, ONE_OTHER),
+            CentralKhmer         => meta("Khmer",                 "ខ្មែរ",              "km", "khm", "khm", "Khmr", LTR, OTHER),
+            Kannada              => meta("Kannada",               "ಕನ್ನಡ",             "kn", "kan", "kan", "Knda", LTR, ONE_OTHER),
+            Korean               => meta("Korean",                "한국어",            "ko", "kor", "kor", "Kore", LTR, OTHER),
+            Kurdish              => meta("Kurdish",               "Kurdî",            "ku", "kur", "kur", "Latn", LTR, ONE_OTHER),
+            Kirghiz              => meta("Kyrgyz",                "кыргызча",         "ky", "kir", "kir", "Cyrl", LTR, ONE_OTHER),
+            Latin                => meta("Latin",                 "Latina",           "la", "lat", "lat", "Latn", LTR, OTHER),
+            Luxembourgish        => meta("Luxembourgish",         "Lëtzebuergesch",   "lb", "ltz", "ltz", "Latn", LTR, ONE_OTHER),
+            Lao                  => meta("Lao",                   "ລາວ",              "lo", "lao", "lao", "Laoo", LTR, OTHER),
+            Lithuanian           => meta("Lithuanian",            "lietuvių",         "lt", "lit", "lit", "Latn", LTR, ONE_FEW_MANY_OTHER),
+            Latvian              => meta("Latvian",               "latviešu",         "lv", "lav", "lav", "Latn", LTR, ZERO_ONE_OTHER),
+            Malagasy             => meta("Malagasy",              "Malagasy",         "mg", "mlg", "mlg", "Latn", LTR, ONE_OTHER),
+            Maori                => meta("Maori",                 "Māori",            "mi", "mri", "mri", "Latn", LTR, OTHER),
+            Macedonian           => meta("Macedonian",            "македонски",       "mk", "mkd", "mkd", "Cyrl", LTR, ONE_OTHER),
+            Malayalam            => meta("Malayalam",             "മലയാളം",           "ml", "mal", "mal", "Mlym", LTR, ONE_OTHER),
+            Mongolian            => meta("Mongolian",             "монгол",           "mn", "mon", "mon", "Cyrl", LTR, ONE_OTHER),
+            Marathi              => meta("Marathi",               "मराठी",             "mr", "mar", "mar", "Deva", LTR, ONE_OTHER),
+            Malay                => meta("Malay",                 "Melayu",           "ms", "msa", "msa", "Latn", LTR, OTHER),
+            Maltese              => meta("Maltese",               "Malti",            "mt", "mlt", "mlt", "Latn", LTR, ONE_TWO_FEW_MANY_OTHER),
+            Burmese              => meta("Burmese",               "မြန်မာ",             "my", "mya", "mya", "Mymr", LTR, OTHER),
+            Nepali               => meta("Nepali",                "नेपाली",             "ne", "nep", "nep", "Deva", LTR, ONE_OTHER),
+            Dutch                => meta("Dutch",                 "Nederlands",       "nl", "nld", "nld", "Latn", LTR, ONE_OTHER),
+            Norwegian            => meta("Norwegian",             "norsk",            "no", "nor", "nor", "Latn", LTR, ONE_OTHER),
+            Chichewa             => meta("Chichewa",              "Chichewa",         "ny", "nya", "nya", "Latn", LTR, ONE_OTHER),
+            Oriya                => meta("Odia",                  "ଓଡ଼ିଆ",              "or", "ori", "ori", "Orya", LTR, ONE_OTHER),
+            Panjabi              => meta("Punjabi",               "ਪੰਜਾਬੀ",            "pa", "pan", "pan", "Guru", LTR, ONE_OTHER),
+            Polish               => meta("Polish",                "polski",           "pl", "pol", "pol", "Latn", LTR, ONE_FEW_MANY_OTHER),
+            Pushto               => meta("Pashto",                "پښتو",             "ps", "pus", "pus", "Arab", RTL, ONE_OTHER),
+            Portuguese           => meta("Portuguese",            "português",        "pt", "por", "por", "Latn", LTR, ONE_MANY_OTHER),
+            Romanian             => meta("Romanian",              "română",           "ro", "ron", "ron", "Latn", LTR, ONE_FEW_OTHER),
+            Russian              => meta("Russian",               "русский",          "ru", "rus", "rus", "Cyrl", LTR, ONE_FEW_MANY_OTHER),
+            Sindhi               => meta("Sindhi",                "سنڌي",             "sd", "snd", "snd", "Arab", RTL, ONE_OTHER),
+            Sinhalese            => meta("Sinhala",               "සිංහල",            "si", "sin", "sin", "Sinh", LTR, ONE_OTHER),
+            Slovak               => meta("Slovak",                "slovenčina",       "sk", "slk", "slk", "Latn", LTR, ONE_FEW_MANY_OTHER),
+            Slovenian            => meta("Slovenian",             "slovenščina",      "sl", "slv", "slv", "Latn", LTR, ONE_TWO_FEW_OTHER),
+            Samoan               => meta("Samoan",                "Gagana Samoa",     "sm", "smo", "smo", "Latn", LTR, OTHER),
+            Shona                => meta("Shona",                 "chiShona",         "sn", "sna", "sna", "Latn", LTR, ONE_OTHER),
+            Somali               => meta("Somali",                "Soomaali",         "so", "som", "som", "Latn", LTR, ONE_OTHER),
+            Albanian             => meta("Albanian",              "shqip",            "sq", "sqi", "sqi", "Latn", LTR, ONE_OTHER),
+            Serbian              => meta("Serbian",               "српски",           "sr", "srp", "srp", "Cyrl", LTR, ONE_FEW_OTHER),
+            SothoSouthern        => meta("Southern Sotho",        "Sesotho",          "st", "sot", "sot", "Latn", LTR, ONE_OTHER),
+            Sundanese            => meta("Sundanese",             "Basa Sunda",       "su", "sun", "sun", "Latn", LTR, OTHER),
+            Swedish              => meta("Swedish",               "svenska",          "sv", "swe", "swe", "Latn", LTR, ONE_OTHER),
+            Swahili              => meta("Swahili",               "Kiswahili",        "sw", "swa", "swa", "Latn", LTR, ONE_OTHER),
+            Tamil                => meta("Tamil",                 "தமிழ்",             "ta", "tam", "tam", "Taml", LTR, ONE_OTHER),
+            Telugu               => meta("Telugu",                "తెలుగు",            "te", "tel", "tel", "Telu", LTR, ONE_OTHER),
+            Tajik                => meta("Tajik",                 "тоҷикӣ",           "tg", "tgk", "tgk", "Cyrl", LTR, OTHER),
+            Thai                 => meta("Thai",                  "ไทย",              "th", "tha", "tha", "Thai", LTR, OTHER),
+            Tagalog              => meta("Tagalog",               "Tagalog",          "tl", "tgl", "tgl", "Latn", LTR, ONE_OTHER),
+            Turkish              => meta("Turkish",               "Türkçe",           "tr", "tur", "tur", "Latn", LTR, ONE_OTHER),
+            Uighur               => meta("Uyghur",                "ئۇيغۇرچە",         "ug", "uig", "uig", "Arab", RTL, ONE_OTHER),
+            Ukrainian            => meta("Ukrainian",             "українська",       "uk", "ukr", "ukr", "Cyrl", LTR, ONE_FEW_MANY_OTHER),
+            Urdu                 => meta("Urdu",                  "اردو",             "ur", "urd", "urd", "Arab", RTL, ONE_OTHER),
+            Uzbek                => meta("Uzbek",                 "oʻzbekcha",        "uz", "uzb", "uzb", "Latn", LTR, ONE_OTHER),
+            Vietnamese           => meta("Vietnamese",            "Tiếng Việt",       "vi", "vie", "vie", "Latn", LTR, OTHER),
+            Xhosa                => meta("Xhosa",                 "isiXhosa",         "xh", "xho", "xho", "Latn", LTR, ONE_OTHER),
+            Yiddish              => meta("Yiddish",               "ייִדיש",            "yi", "yid", "yid", "Hebr", RTL, ONE_OTHER),
+            Yoruba               => meta("Yoruba",                "Èdè Yorùbá",       "yo", "yor", "yor", "Latn", LTR, OTHER),
+            SimpleChinese        => meta("Chinese (Simplified)",  "简体中文",          "zh", "zho", "zho", "Hans", LTR, OTHER),
+            TraditionalChinese   => meta("Chinese (Traditional)", "繁體中文",          "zh", "zho", "zho", "Hant", LTR, OTHER),
+            Zulu                 => meta("Zulu",                  "isiZulu",          "zu", "zul", "zul", "Latn", LTR, ONE_OTHER),
+        };
+        Some(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_metadata() {
+        use super::{Direction, PluralCategory};
+        use crate::Language;
+
+        assert!(Language::Auto.metadata().is_none());
+
+        let arabic = Language::Arabic.metadata().unwrap();
+        assert_eq!(arabic.native_name(), "العربية");
+        assert_eq!(arabic.direction(), Direction::RightToLeft);
+        assert_eq!(arabic.plural_categories().len(), 6);
+
+        let russian = Language::Russian.metadata().unwrap();
+        assert_eq!(russian.iso639_2(), "rus");
+        assert_eq!(russian.script(), "Cyrl");
+        assert!(!russian.is_rtl());
+        assert!(russian.plural_categories().contains(&PluralCategory::Few));
+
+        assert_eq!(Language::TraditionalChinese.metadata().unwrap().script(), "Hant");
+        assert_eq!(Language::Japanese.metadata().unwrap().plural_categories(), &[PluralCategory::Other]);
+    }
+
+    #[test]
+    fn test_metadata_codes_match_abbreviation() {
+        use crate::Language;
+        use crate::LanguageTag;
+
+        for code in ["af", "he", "no", "zh-CN", "zh-TW", "zu"] {
+            let language = Language::from(code).unwrap();
+            let tag = LanguageTag::from(language);
+            assert_eq!(language.metadata().unwrap().iso639_1(), tag.language());
+        }
+    }
+}
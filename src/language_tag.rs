@@ -0,0 +1,233 @@
+use crate::Error;
+use crate::Result;
+use crate::Language;
+
+// Deprecated or provider specific subtags and their canonical replacement.
+// Google still returns `iw` for Hebrew and `jw` for Javanese.
+const LANGUAGE_ALIASES: [(&str, &str); 5] = [
+    ("iw", "he"),
+    ("in", "id"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("mo", "ro"),
+];
+
+// Subtags that have no variant of their own in `Language` but are close enough to one.
+const LANGUAGE_FALLBACKS: [(&str, Language); 4] = [
+    ("nb", Language::Norwegian),
+    ("nn", Language::Norwegian),
+    ("fil", Language::Tagalog),
+    ("pes", Language::Persian),
+];
+
+/// A parsed and canonicalised BCP-47 language tag such as `zh-Hans-CN` or `pt-BR`.
+///
+/// Tags that do not map to a `Language` are still kept, so the original answer of a service is never lost.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag {
+    language: String,
+    script: Option<String>,
+    region: Option<String>,
+    variants: Vec<String>,
+    extensions: Vec<String>,
+}
+
+impl LanguageTag {
+    pub fn parse(tag: &str) -> Result<LanguageTag> {
+        let invalid = || Error::InvalidLanguage(tag.to_string());
+
+        let lower = tag.trim().replace('_', "-").to_ascii_lowercase();
+        let mut subtags = lower.split('-').peekable();
+
+        let Some(language) = subtags.next() else {
+            return Err(invalid());
+        };
+        if !(2..=8).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(invalid());
+        }
+        let mut language = language.to_string();
+
+        // Extended language subtag, the canonical form drops the prefix: `zh-yue` => `yue`
+        if let Some(extlang) = subtags.peek() {
+            if extlang.len() == 3 && extlang.chars().all(|c| c.is_ascii_alphabetic()) {
+                language = extlang.to_string();
+                subtags.next();
+            }
+        }
+        if let Some((_, canonical)) = LANGUAGE_ALIASES.iter().find(|(alias, _)| *alias == language) {
+            language = canonical.to_string();
+        }
+
+        let mut result = LanguageTag { language, script: None, region: None, variants: vec![], extensions: vec![] };
+
+        if let Some(script) = subtags.peek() {
+            if script.len() == 4 && script.chars().all(|c| c.is_ascii_alphabetic()) {
+                result.script = Some(script[..1].to_ascii_uppercase() + &script[1..]);
+                subtags.next();
+            }
+        }
+
+        if let Some(region) = subtags.peek() {
+            let alpha = region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic());
+            let digit = region.len() == 3 && region.chars().all(|c| c.is_ascii_digit());
+            if alpha || digit {
+                result.region = Some(region.to_ascii_uppercase());
+                subtags.next();
+            }
+        }
+
+        while let Some(subtag) = subtags.peek() {
+            let alnum = subtag.chars().all(|c| c.is_ascii_alphanumeric());
+            let long = (5..=8).contains(&subtag.len());
+            let short = subtag.len() == 4 && subtag.starts_with(|c: char| c.is_ascii_digit());
+            if !alnum || !(long || short) {
+                break;
+            }
+            result.variants.push(subtag.to_string());
+            subtags.next();
+        }
+
+        // Extensions and private use subtags are kept verbatim
+        for subtag in subtags {
+            if subtag.is_empty() || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(invalid());
+            }
+            result.extensions.push(subtag.to_string());
+        }
+        if result.extensions.len() == 1 {
+            return Err(invalid());
+        }
+
+        Ok(result)
+    }
+
+    /// The primary language subtag, lowercase.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The script subtag in title case, e.g. `Hant`.
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// The region subtag in upper case, e.g. `BR`.
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn variants(&self) -> &[String] {
+        &self.variants
+    }
+
+    /// The closest `Language`, falling back from the full tag to the language subtag.
+    pub fn to_language(&self) -> Option<Language> {
+        if self.language == "zh" || self.language == "cmn" {
+            let traditional = matches!(self.script(), Some("Hant"))
+                || (self.script().is_none() && matches!(self.region(), Some("TW" | "HK" | "MO")));
+            return Some(if traditional { Language::TraditionalChinese } else { Language::SimpleChinese });
+        }
+        if let Some((_, language)) = LANGUAGE_FALLBACKS.iter().find(|(subtag, _)| *subtag == self.language) {
+            return Some(*language);
+        }
+        match Language::from(&self.language) {
+            Some(Language::Auto) => None,
+            language => language,
+        }
+    }
+}
+
+impl std::fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.language)?;
+        if let Some(script) = &self.script {
+            write!(f, "-{}", script)?;
+        }
+        if let Some(region) = &self.region {
+            write!(f, "-{}", region)?;
+        }
+        for subtag in self.variants.iter().chain(self.extensions.iter()) {
+            write!(f, "-{}", subtag)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for LanguageTag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        LanguageTag::parse(s)
+    }
+}
+
+impl From<Language> for LanguageTag {
+    fn from(language: Language) -> Self {
+        LanguageTag::parse(language.abbreviation().unwrap()).unwrap()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LanguageTag {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LanguageTag {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let tag = String::deserialize(deserializer)?;
+        tag.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse() {
+        use super::LanguageTag;
+
+        let tag = LanguageTag::parse("zh_hans_cn").unwrap();
+        assert_eq!(tag.language(), "zh");
+        assert_eq!(tag.script(), Some("Hans"));
+        assert_eq!(tag.region(), Some("CN"));
+        assert_eq!(tag.to_string(), "zh-Hans-CN");
+
+        assert_eq!(LanguageTag::parse("iw").unwrap().to_string(), "he");
+        assert_eq!(LanguageTag::parse("jw").unwrap().to_string(), "jv");
+        assert_eq!(LanguageTag::parse("de-CH-1996").unwrap().variants(), &["1996".to_string()]);
+        assert_eq!(LanguageTag::parse("en-US-x-twain").unwrap().to_string(), "en-US-x-twain");
+        assert_eq!(LanguageTag::parse("es-419").unwrap().region(), Some("419"));
+        assert_eq!(LanguageTag::parse("zh-yue-HK").unwrap().to_string(), "yue-HK");
+
+        assert!(LanguageTag::parse("").is_err());
+        assert!(LanguageTag::parse("e").is_err());
+        assert!(LanguageTag::parse("en--US").is_err());
+        assert!(LanguageTag::parse("en-US-x").is_err());
+    }
+
+    #[test]
+    fn test_to_language() {
+        use super::LanguageTag;
+        use crate::Language;
+
+        let language = |tag: &str| LanguageTag::parse(tag).unwrap().to_language();
+
+        assert_eq!(language("zh-CN"), Some(Language::SimpleChinese));
+        assert_eq!(language("zh-Hans"), Some(Language::SimpleChinese));
+        assert_eq!(language("zh"), Some(Language::SimpleChinese));
+        assert_eq!(language("zh-TW"), Some(Language::TraditionalChinese));
+        assert_eq!(language("zh-Hant-CN"), Some(Language::TraditionalChinese));
+        assert_eq!(language("zh-HK"), Some(Language::TraditionalChinese));
+        assert_eq!(language("pt-BR"), Some(Language::Portuguese));
+        assert_eq!(language("en-US"), Some(Language::English));
+        assert_eq!(language("iw"), Some(Language::Hebrew));
+        assert_eq!(language("nb-NO"), Some(Language::Norwegian));
+        assert_eq!(language("zh-cmn-Hant"), Some(Language::TraditionalChinese));
+        assert_eq!(language("jw"), None);
+        assert_eq!(language("auto"), None);
+
+        assert_eq!(LanguageTag::from(Language::TraditionalChinese).to_string(), "zh-TW");
+    }
+}
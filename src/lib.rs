@@ -1,16 +1,65 @@
 mod api;
 mod error;
 mod language;
+mod language_tag;
+mod language_metadata;
 mod translator;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 
 pub use self::language::Language;
-pub use self::api::Translation;
+pub use self::language_tag::LanguageTag;
+pub use self::language_metadata::{
+    LanguageMetadata,
+    Direction,
+    PluralCategory
+};
+pub use self::api::{
+    Translation,
+    Detection,
+    Consensus,
+    Capabilities,
+    LanguageSet,
+    HttpConfig,
+    DetectorAPI,
+    TranslatorAPI,
+    Request,
+    Transport,
+    ReqwestTransport,
+    HttpRequest,
+    HttpResponse,
+    Cassette,
+    CassetteMode
+};
+#[cfg(feature = "testing")]
+pub use self::api::{
+    Conformance,
+    ConformanceStub,
+    Check,
+    MockTranslator,
+    MockDetector
+};
 pub use self::error::{
     Error,
     Result
 };
 pub use self::translator::{
     Strategy,
+    Selection,
+    Backoff,
+    BackoffPolicy,
+    Hedging,
+    ServiceHealth,
+    ServiceState,
+    Attempt,
+    Outcome,
+    TranslateOptions,
+    RateLimit,
+    Quota,
     TranslatorType,
     TranslatorBuilder,
     Translator,
@@ -18,4 +67,7 @@ pub use self::translator::{
     Detector,
     translate,
     language
-};
\ No newline at end of file
+};
+pub use tokio_util::sync::CancellationToken;
+#[cfg(feature = "server")]
+pub use self::server::Server;
@@ -0,0 +1,490 @@
+use crate::Result;
+use crate::Error;
+use crate::Language;
+use crate::Translator;
+use crate::Detector;
+
+use std::collections::{
+    HashMap,
+    VecDeque
+};
+use std::ops::{
+    Deref,
+    DerefMut
+};
+use std::sync::Arc;
+use axum::extract::{
+    FromRequest,
+    Request,
+    State
+};
+use axum::http::{
+    header,
+    StatusCode
+};
+use axum::response::{
+    IntoResponse,
+    Response
+};
+use axum::routing::{
+    get,
+    post
+};
+use axum::{
+    Form,
+    Json,
+    Router
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{
+    Mutex,
+    Semaphore,
+    SemaphorePermit
+};
+
+const DEFAULT_CACHE_SIZE: usize = 1_000;
+
+/// An HTTP server with the JSON API of LibreTranslate (`/translate`, `/detect` and `/languages`),
+/// so that its clients and browser extensions can use a `Translator` and a `Detector`.
+///
+/// Requests go through the failover of the dispatcher, and translations are cached in memory.
+///
+/// A translator or detector handles one request at a time, the others wait for it. Add more of them with
+/// `translator` and `detector` to handle requests concurrently, each one keeps the health of its own services.
+pub struct Server {
+    translators: Vec<Translator>,
+    detectors: Vec<Detector>,
+    cache_size: usize,
+}
+
+impl Server {
+    pub fn new(translator: Translator, detector: Detector) -> Self {
+        Self { translators: vec![translator], detectors: vec![detector], cache_size: DEFAULT_CACHE_SIZE }
+    }
+
+    /// Another translator, to handle one more translation at a time.
+    pub fn translator(mut self, translator: Translator) -> Self {
+        self.translators.push(translator);
+        self
+    }
+
+    /// Another detector, to handle one more detection at a time.
+    pub fn detector(mut self, detector: Detector) -> Self {
+        self.detectors.push(detector);
+        self
+    }
+
+    /// How many translations are kept, the oldest are dropped first. `0` disables the cache.
+    pub fn cache_size(mut self, cache_size: usize) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    /// The routes of the API, to be served by `serve` or nested in an application of your own.
+    pub fn router(self) -> Router {
+        let languages = languages(&self.translators[0]);
+        let state = Arc::new(Shared {
+            translators: Pool::new(self.translators),
+            detectors: Pool::new(self.detectors),
+            cache: Mutex::new(Cache::new(self.cache_size)),
+            languages: languages,
+        });
+        Router::new()
+            .route("/translate", post(translate))
+            .route("/detect", post(detect))
+            .route("/languages", get(list_languages))
+            .with_state(state)
+    }
+
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> Result<()> {
+        axum::serve(listener, self.router()).await.map_err(|e| Error::IOError(e.to_string()))
+    }
+}
+
+// Each request takes a translator or a detector out of its pool, which keeps the state of the services
+struct Shared {
+    translators: Pool<Translator>,
+    detectors: Pool<Detector>,
+    cache: Mutex<Cache>,
+    languages: serde_json::Value,
+}
+
+// Lends its items one request at a time, a request waits while they are all lent
+struct Pool<T> {
+    items: std::sync::Mutex<Vec<T>>,
+    available: Semaphore,
+}
+
+impl<T> Pool<T> {
+    fn new(items: Vec<T>) -> Self {
+        Self { available: Semaphore::new(items.len()), items: std::sync::Mutex::new(items) }
+    }
+
+    async fn get(&self) -> Lent<'_, T> {
+        let permit = self.available.acquire().await.expect("the semaphore is never closed");
+        let item = self.items.lock().unwrap().pop().expect("a permit is held for each item");
+        Lent { pool: self, item: Some(item), _permit: permit }
+    }
+}
+
+// Goes back to the pool when dropped, before its permit is released
+struct Lent<'a, T> {
+    pool: &'a Pool<T>,
+    item: Option<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for Lent<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.item.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for Lent<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.item.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for Lent<'_, T> {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.pool.items.lock().unwrap().push(item);
+        }
+    }
+}
+
+struct Cache {
+    capacity: usize,
+    entries: HashMap<(String, Language, Language), (String, Option<Language>)>,
+    order: VecDeque<(String, Language, Language)>,
+}
+
+impl Cache {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, entries: HashMap::new(), order: VecDeque::new() }
+    }
+
+    fn get(&self, key: &(String, Language, Language)) -> Option<(String, Option<Language>)> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: (String, Language, Language), value: (String, Option<Language>)) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+    }
+}
+
+// `q` is a text, or a list of texts translated together
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Query {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct TranslateParams {
+    q: Query,
+    #[serde(default)]
+    source: Option<String>,
+    target: String,
+}
+
+#[derive(Deserialize)]
+struct DetectParams {
+    q: String,
+}
+
+// LibreTranslate answers errors as `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let status = match e {
+            Error::InvalidLanguage(_) | Error::UnsupportedLanguage(_) | Error::UnsupportedDirection(..)
+                | Error::TargetEqualToSource | Error::TargetLanguageIsAuto | Error::LengthLimit(_) => StatusCode::BAD_REQUEST,
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::NoAvailableService { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+// Clients send the parameters either as JSON or as a form
+async fn params<T: serde::de::DeserializeOwned + 'static>(request: Request) -> std::result::Result<T, ApiError> {
+    let json = request.headers().get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let result = match json {
+        true => Json::<T>::from_request(request, &()).await.map(|Json(params)| params).map_err(|e| e.body_text()),
+        false => Form::<T>::from_request(request, &()).await.map(|Form(params)| params).map_err(|e| e.body_text()),
+    };
+    result.map_err(|e| ApiError(StatusCode::BAD_REQUEST, e))
+}
+
+fn language(code: &str) -> std::result::Result<Language, ApiError> {
+    code.parse().map_err(|e: Error| ApiError::from(e))
+}
+
+// The services answer empty text with an empty result, which is the client's mistake rather than theirs
+fn not_empty(text: &str) -> std::result::Result<(), ApiError> {
+    match text.trim().is_empty() {
+        true => Err(ApiError(StatusCode::BAD_REQUEST, "`q` is empty".to_string())),
+        false => Ok(()),
+    }
+}
+
+async fn translate(State(state): State<Arc<Shared>>, request: Request) -> std::result::Result<Json<serde_json::Value>, ApiError> {
+    let params: TranslateParams = params(request).await?;
+    let source = language(params.source.as_deref().unwrap_or("auto"))?;
+    let target = language(&params.target)?;
+
+    let texts = match &params.q {
+        Query::One(text) => vec![text.clone()],
+        Query::Many(texts) => texts.clone(),
+    };
+    texts.iter().try_for_each(|text| not_empty(text))?;
+    let mut results = vec![];
+    for text in texts {
+        let key = (text.clone(), source, target);
+        let cached = state.cache.lock().await.get(&key);
+        let result = match cached {
+            Some(result) => result,
+            None => {
+                let translation = state.translators.get().await.translate(&text, source, target).await?;
+                let result = (translation.result().to_string(), translation.source());
+                state.cache.lock().await.insert(key, result.clone());
+                result
+            },
+        };
+        results.push(result);
+    }
+
+    let translated = match params.q {
+        Query::One(_) => json!(results[0].0),
+        Query::Many(_) => json!(results.iter().map(|(result, _)| result).collect::<Vec<_>>()),
+    };
+    let mut response = json!({ "translatedText": translated });
+    // The services report the source they detected without a confidence
+    if source == Language::Auto {
+        let detected = |detected: &Option<Language>| json!({
+            "language": detected.map(|language| language.to_string()),
+            "confidence": if detected.is_some() { 100.0 } else { 0.0 },
+        });
+        response["detectedLanguage"] = match params.q {
+            Query::One(_) => detected(&results[0].1),
+            Query::Many(_) => json!(results.iter().map(|(_, source)| detected(source)).collect::<Vec<_>>()),
+        };
+    }
+    Ok(Json(response))
+}
+
+async fn detect(State(state): State<Arc<Shared>>, request: Request) -> std::result::Result<Json<serde_json::Value>, ApiError> {
+    let params: DetectParams = params(request).await?;
+    not_empty(&params.q)?;
+    let consensus = state.detectors.get().await.consensus(&params.q).await?;
+    Ok(Json(json!([{
+        "language": consensus.language().to_string(),
+        "confidence": consensus.confidence() * 100.0,
+    }])))
+}
+
+async fn list_languages(State(state): State<Arc<Shared>>) -> Json<serde_json::Value> {
+    Json(state.languages.clone())
+}
+
+// Every language with the targets that at least one service supports from it
+fn languages(translator: &Translator) -> serde_json::Value {
+    let capabilities = translator.capabilities();
+    let languages: Vec<Language> = Language::all().iter().copied().filter(|language| *language != Language::Auto).collect();
+    let languages: Vec<serde_json::Value> = languages.iter()
+        .map(|source| {
+            let targets: Vec<String> = languages.iter()
+                .filter(|target| *target != source)
+                .filter(|target| capabilities.iter().any(|(_, capabilities)| capabilities.supports(*source, **target)))
+                .map(|target| target.to_string())
+                .collect();
+            let name = source.metadata().map(|metadata| metadata.english_name()).unwrap_or_default();
+            json!({ "code": source.to_string(), "name": name, "targets": targets })
+        })
+        .collect();
+    json!(languages)
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_server() {
+        use crate::{Detector, Language, Result, Strategy, Translator, Translation};
+        use crate::api::{DetectorAPI, Request, TranslatorAPI};
+        use super::Server;
+        use async_trait::async_trait;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Upper-cases the text, and counts the requests
+        struct Upper(Arc<AtomicUsize>);
+
+        #[async_trait]
+        impl TranslatorAPI for Upper {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+        }
+
+        struct French;
+
+        #[async_trait]
+        impl DetectorAPI for French {
+            async fn language(&self, _request: &Request, _text: &str) -> Result<Language> {
+                Ok(Language::French)
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let translator = Translator::builder()
+            .strategy(Strategy::Single("upper".to_string()))
+            .service("upper", Upper(calls.clone()), 100)
+            .build()
+            .unwrap();
+        let detector = Detector::builder()
+            .strategy(Strategy::Single("french".to_string()))
+            .service("french", French, 100)
+            .build()
+            .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(Server::new(translator, detector).serve(listener));
+        // Sends `body` with its content type, and returns the status with the parsed answer
+        let send = |path: &str, content_type: &str, body: String| {
+            let request = reqwest::Client::new().post(format!("{}{}", url, path)).header("Content-Type", content_type).body(body);
+            async move {
+                let response = request.send().await.unwrap();
+                let status = response.status().as_u16();
+                (status, serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap())
+            }
+        };
+        let post = |path: &str, body: serde_json::Value| send(path, "application/json", body.to_string());
+
+        let (_, response) = post("/translate", serde_json::json!({ "q": "hello", "source": "auto", "target": "de" })).await;
+        assert_eq!(response["translatedText"], "HELLO");
+        assert_eq!(response["detectedLanguage"]["language"], "en");
+
+        // Forms are accepted too, and the cached translation is not requested again
+        let (_, response) = send("/translate", "application/x-www-form-urlencoded", "q=hello&source=auto&target=de".to_string()).await;
+        assert_eq!(response["translatedText"], "HELLO");
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let (_, response) = post("/translate", serde_json::json!({ "q": ["a", "b"], "source": "en", "target": "de" })).await;
+        assert_eq!(response["translatedText"], serde_json::json!(["A", "B"]));
+        assert!(response.get("detectedLanguage").is_none());
+
+        let (status, response) = post("/translate", serde_json::json!({ "q": "hello", "target": "xx" })).await;
+        assert_eq!(status, 400);
+        assert!(response["error"].is_string());
+
+        let (status, _) = post("/translate", serde_json::json!({ "q": " ", "target": "de" })).await;
+        assert_eq!(status, 400);
+        let (status, _) = post("/detect", serde_json::json!({ "q": "" })).await;
+        assert_eq!(status, 400);
+
+        let (_, response) = post("/detect", serde_json::json!({ "q": "bonjour" })).await;
+        assert_eq!(response[0]["language"], "fr");
+        assert_eq!(response[0]["confidence"], 100.0);
+
+        let response = reqwest::get(format!("{}/languages", url)).await.unwrap().text().await.unwrap();
+        let response = serde_json::from_str::<serde_json::Value>(&response).unwrap();
+        let english = response.as_array().unwrap().iter().find(|language| language["code"] == "en").unwrap();
+        assert_eq!(english["name"], "English");
+        assert!(english["targets"].as_array().unwrap().contains(&serde_json::json!("de")));
+        assert!(!english["targets"].as_array().unwrap().contains(&serde_json::json!("en")));
+    }
+
+    #[tokio::test]
+    async fn test_server_pool() {
+        use crate::{Detector, Language, Result, Strategy, Translator, Translation};
+        use crate::api::{Request, TranslatorAPI};
+        use super::Server;
+        use async_trait::async_trait;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+        use tokio::sync::Barrier;
+
+        // Counts the translations in flight, and with a barrier only answers once enough are in flight together
+        #[derive(Clone)]
+        struct Counted {
+            in_flight: Arc<AtomicUsize>,
+            max: Arc<AtomicUsize>,
+            barrier: Option<Arc<Barrier>>,
+        }
+
+        #[async_trait]
+        impl TranslatorAPI for Counted {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(now, Ordering::SeqCst);
+                match &self.barrier {
+                    Some(barrier) => { barrier.wait().await; },
+                    None => tokio::time::sleep(Duration::from_millis(50)).await,
+                }
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+        }
+
+        // A server with two translators, returns the URL of `/translate`
+        let serve = |api: Counted| async move {
+            let translator = || Translator::builder()
+                .strategy(Strategy::Single("counted".to_string()))
+                .service("counted", api.clone(), 100)
+                .build()
+                .unwrap();
+            let server = Server::new(translator(), Detector::builder().build().unwrap()).translator(translator());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/translate", listener.local_addr().unwrap());
+            tokio::spawn(server.serve(listener));
+            url
+        };
+        let post = |url: &str, text: &str| reqwest::Client::new().post(url)
+            .header("Content-Type", "application/json")
+            .body(serde_json::json!({ "q": text, "source": "en", "target": "de" }).to_string())
+            .send();
+
+        // Both translators answer at once: each request only returns once the other one is in flight as well
+        let api = Counted { in_flight: Arc::default(), max: Arc::default(), barrier: Some(Arc::new(Barrier::new(2))) };
+        let url = serve(api.clone()).await;
+        let both = async { tokio::join!(post(&url, "one"), post(&url, "two")) };
+        let (first, second) = tokio::time::timeout(Duration::from_secs(10), both).await.expect("the requests waited for each other");
+        assert_eq!(first.unwrap().status(), 200);
+        assert_eq!(second.unwrap().status(), 200);
+
+        // Three requests for two translators, one of them waits
+        let api = Counted { in_flight: Arc::default(), max: Arc::default(), barrier: None };
+        let url = serve(api.clone()).await;
+        let (first, second, third) = tokio::join!(post(&url, "three"), post(&url, "four"), post(&url, "five"));
+        assert!(first.unwrap().status() == 200 && second.unwrap().status() == 200 && third.unwrap().status() == 200);
+        assert!(api.max.load(Ordering::SeqCst) <= 2);
+    }
+}
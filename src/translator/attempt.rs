@@ -0,0 +1,52 @@
+use crate::Error;
+use crate::translator::health::ServiceState;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Succeeded,
+    Failed(Error),
+    /// Dropped before it answered: another service answered first, see `Hedging`,
+    /// or the call was cancelled or ran out of time.
+    Cancelled,
+}
+
+/// One request sent to a service while dispatching a call, with the status change it caused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub(crate) service: String,
+    pub(crate) duration: Duration,
+    pub(crate) outcome: Outcome,
+    pub(crate) state_before: ServiceState,
+    pub(crate) state_after: ServiceState,
+}
+
+impl Attempt {
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn outcome(&self) -> &Outcome {
+        &self.outcome
+    }
+
+    pub fn error(&self) -> Option<&Error> {
+        match &self.outcome {
+            Outcome::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn state_before(&self) -> ServiceState {
+        self.state_before
+    }
+
+    pub fn state_after(&self) -> ServiceState {
+        self.state_after
+    }
+}
@@ -0,0 +1,269 @@
+use crate::translator::dispatcher::ServiceStatus;
+
+use std::time::{
+    Duration,
+    Instant
+};
+use rand::{
+    thread_rng,
+    Rng
+};
+
+#[derive(Debug, Clone)]
+pub enum Backoff {
+    /// Fixed blocking periods, the last one is repeated.
+    Ladder(Vec<Duration>),
+    /// `base * factor^(n - 1)` for the n-th consecutive block, capped at `max`. A negative result counts as no block.
+    Exponential {
+        base: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+/// How a service is taken out of rotation after errors and brought back.
+///
+/// A failing service first gets `retries` short retries, then it is blocked for increasingly long periods.
+/// Once a block expires the service is half-open: it is tried again and `probe_successes` consecutive
+/// successes close the breaker and reset it, while a single failure blocks it for the next period.
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    error_window: Duration,
+    failure_threshold: Option<u32>,
+    retries: u32,
+    retry_delay: Duration,
+    backoff: Backoff,
+    jitter: f64,
+    probe_successes: u32,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            error_window: Duration::from_millis(1_000),
+            failure_threshold: None,
+            retries: 3,
+            retry_delay: Duration::from_millis(1_000),
+            backoff: Backoff::Ladder(vec![
+                Duration::from_millis(3_000),
+                Duration::from_millis(60_000),
+                Duration::from_millis(300_000),
+                Duration::from_millis(3_600_000),
+            ]),
+            jitter: 0.0,
+            probe_successes: 1,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Errors are tolerated until the first one of a streak is older than `error_window`.
+    pub fn error_window(mut self, error_window: Duration) -> Self {
+        self.error_window = error_window;
+        self
+    }
+
+    /// Also trip after this many consecutive failures, even within the error window.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = Some(failure_threshold.max(1));
+        self
+    }
+
+    pub fn retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Randomly stretches or shrinks every delay by up to this fraction, `0.0..=1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Consecutive successes needed in the half-open state before the service is `Ready` again.
+    pub fn probe_successes(mut self, probe_successes: u32) -> Self {
+        self.probe_successes = probe_successes.max(1);
+        self
+    }
+
+    pub(crate) fn should_trip(&self, first_error: Instant, consecutive_failures: u32, now: Instant) -> bool {
+        if let Some(threshold) = self.failure_threshold {
+            if consecutive_failures >= threshold {
+                return true;
+            }
+        }
+        now - first_error > self.error_window
+    }
+
+    pub(crate) fn block_duration(&self, level: u32) -> Duration {
+        let level = level.max(1);
+        match &self.backoff {
+            Backoff::Ladder(ladder) => {
+                let Some(last) = ladder.last() else {
+                    return Duration::from_secs(0);
+                };
+                *ladder.get(level as usize - 1).unwrap_or(last)
+            },
+            Backoff::Exponential { base, factor, max } => {
+                let secs = base.as_secs_f64() * factor.powi(level as i32 - 1);
+                if !secs.is_finite() || secs >= max.as_secs_f64() {
+                    return *max;
+                }
+                Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(*max)
+            },
+        }
+    }
+
+    fn with_jitter(&self, delay: Duration) -> Duration {
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 + thread_rng().gen_range(-self.jitter..=self.jitter))
+    }
+
+    fn longest_block(&self) -> Duration {
+        match &self.backoff {
+            Backoff::Ladder(ladder) => ladder.iter().max().copied().unwrap_or_default(),
+            Backoff::Exponential { max, .. } => *max,
+        }
+    }
+
+    pub(crate) fn on_failure(&self, status: &ServiceStatus, now: Instant) -> ServiceStatus {
+        let block = |level: u32| ServiceStatus::Blocking(level, now + self.with_jitter(self.block_duration(level)));
+        match *status {
+            ServiceStatus::Ready if self.retries > 0 => ServiceStatus::Retry((self.retries, now + self.with_jitter(self.retry_delay))),
+            ServiceStatus::Ready => block(1),
+            ServiceStatus::Retry((n, _)) if n > 1 => ServiceStatus::Retry((n - 1, now + self.with_jitter(self.retry_delay))),
+            ServiceStatus::Retry(_) => block(1),
+            ServiceStatus::Blocking(level, _) | ServiceStatus::HalfOpen(level, _) => block(level.saturating_add(1)),
+        }
+    }
+
+    // Keeps the position in the ladder but waits at least `delay`, as requested by the service.
+    // A delay too long for an `Instant` is cut to the longest block.
+    pub(crate) fn on_rate_limited(&self, status: &ServiceStatus, now: Instant, delay: Duration) -> ServiceStatus {
+        let until = now.checked_add(delay).unwrap_or_else(|| now + self.longest_block());
+        match *status {
+            ServiceStatus::Ready => ServiceStatus::Retry((self.retries.max(1), until)),
+            ServiceStatus::Retry((n, next)) => ServiceStatus::Retry((n, next.max(until))),
+            ServiceStatus::Blocking(level, end) => ServiceStatus::Blocking(level, end.max(until)),
+            ServiceStatus::HalfOpen(level, _) => ServiceStatus::Blocking(level, until),
+        }
+    }
+
+    pub(crate) fn on_success(&self, status: &ServiceStatus) -> ServiceStatus {
+        let (level, successes) = match *status {
+            ServiceStatus::Blocking(level, _) => (level, 1),
+            ServiceStatus::HalfOpen(level, successes) => (level, successes + 1),
+            _ => return ServiceStatus::Ready,
+        };
+        if successes >= self.probe_successes {
+            ServiceStatus::Ready
+        } else {
+            ServiceStatus::HalfOpen(level, successes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{
+        Duration,
+        Instant
+    };
+
+    #[test]
+    fn test_default_ladder() {
+        use super::BackoffPolicy;
+        use crate::translator::dispatcher::ServiceStatus;
+
+        let policy = BackoffPolicy::default();
+        let now = Instant::now();
+
+        let mut status = ServiceStatus::Ready;
+        for n in (1..=3).rev() {
+            status = policy.on_failure(&status, now);
+            assert!(matches!(status, ServiceStatus::Retry((left, next)) if left == n && next == now + Duration::from_secs(1)));
+        }
+        for (level, secs) in [(1, 3), (2, 60), (3, 300), (4, 3_600), (5, 3_600)] {
+            status = policy.on_failure(&status, now);
+            assert!(matches!(status, ServiceStatus::Blocking(l, end) if l == level && end == now + Duration::from_secs(secs)));
+        }
+        assert!(matches!(policy.on_success(&status), ServiceStatus::Ready));
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        use super::{Backoff, BackoffPolicy};
+        use crate::translator::dispatcher::ServiceStatus;
+
+        let policy = BackoffPolicy::new()
+            .retries(0, Duration::from_secs(0))
+            .backoff(Backoff::Exponential { base: Duration::from_secs(1), factor: 2.0, max: Duration::from_secs(5) })
+            .failure_threshold(2)
+            .probe_successes(2);
+        let now = Instant::now();
+
+        assert!(!policy.should_trip(now, 1, now));
+        assert!(policy.should_trip(now, 2, now));
+        assert!(policy.should_trip(now, 1, now + Duration::from_secs(2)));
+
+        assert_eq!(policy.block_duration(1), Duration::from_secs(1));
+        assert_eq!(policy.block_duration(3), Duration::from_secs(4));
+        assert_eq!(policy.block_duration(10), Duration::from_secs(5));
+
+        let negative = BackoffPolicy::new().backoff(Backoff::Exponential { base: Duration::from_secs(1), factor: -2.0, max: Duration::from_secs(5) });
+        assert_eq!(negative.block_duration(2), Duration::from_secs(0));
+        assert_eq!(negative.block_duration(3), Duration::from_secs(4));
+
+        let status = policy.on_failure(&ServiceStatus::Ready, now);
+        assert!(matches!(status, ServiceStatus::Blocking(1, _)));
+
+        let status = policy.on_success(&status);
+        assert!(matches!(status, ServiceStatus::HalfOpen(1, 1)));
+        assert!(matches!(policy.on_failure(&status, now), ServiceStatus::Blocking(2, _)));
+        assert!(matches!(policy.on_success(&status), ServiceStatus::Ready));
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        use super::BackoffPolicy;
+        use crate::translator::dispatcher::ServiceStatus;
+
+        let policy = BackoffPolicy::default();
+        let now = Instant::now();
+        let delay = Duration::from_secs(30);
+
+        assert!(matches!(policy.on_rate_limited(&ServiceStatus::Ready, now, delay), ServiceStatus::Retry((3, next)) if next == now + delay));
+        assert!(matches!(policy.on_rate_limited(&ServiceStatus::Retry((2, now)), now, delay), ServiceStatus::Retry((2, next)) if next == now + delay));
+        let end = now + Duration::from_secs(60);
+        assert!(matches!(policy.on_rate_limited(&ServiceStatus::Blocking(2, end), now, delay), ServiceStatus::Blocking(2, e) if e == end));
+        let longest = now + Duration::from_millis(3_600_000);
+        assert!(matches!(policy.on_rate_limited(&ServiceStatus::Ready, now, Duration::MAX), ServiceStatus::Retry((3, next)) if next == longest));
+    }
+
+    #[test]
+    fn test_jitter() {
+        use super::BackoffPolicy;
+        use crate::translator::dispatcher::ServiceStatus;
+
+        let policy = BackoffPolicy::new().retries(0, Duration::from_secs(0)).jitter(0.5);
+        let now = Instant::now();
+        for _ in 0..100 {
+            let ServiceStatus::Blocking(_, end) = policy.on_failure(&ServiceStatus::Ready, now) else {
+                panic!("expected a block");
+            };
+            assert!(end >= now + Duration::from_millis(1_500) && end <= now + Duration::from_millis(4_500));
+        }
+    }
+}
@@ -1,8 +1,14 @@
 use crate::Translation;
+use crate::api::{
+    Detection,
+    Consensus,
+    Capabilities
+};
 use crate::error::Error;
 use crate::{
     Language,
-    Result
+    Result,
+    Selection
 };
 use crate::api::{
     DetectorAPIContainer,
@@ -11,6 +17,29 @@ use crate::api::{
     TranslatorAPI,
     Request
 };
+use crate::translator::backoff::BackoffPolicy;
+use crate::translator::attempt::{
+    Attempt,
+    Outcome
+};
+use crate::translator::health::{
+    ServiceHealth,
+    ServiceState
+};
+use crate::translator::options::TranslateOptions;
+use crate::translator::hedging::{
+    Hedging,
+    LatencyWindow
+};
+use crate::translator::state::{
+    Clock,
+    STATE_VERSION
+};
+use crate::translator::rate_limit::{
+    Quota,
+    RateLimit,
+    RateLimiter
+};
 use crate::api::google::google_translate::{
     API_MobileGoogleTranslate,
     API_GoogleTranslateExtensions,
@@ -19,21 +48,32 @@ use crate::api::google::google_translate::{
 
 use std::convert::Into;
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::sync::{
+    Arc,
+    OnceLock
+};
 use std::cmp::max;
 use std::time::{
     Duration,
-    Instant
+    Instant,
+    SystemTime
 };
 use rand::{
     thread_rng,
     Rng
 };
+use futures::future::{
+    join_all,
+    pending,
+    select_all
+};
+use tokio_util::sync::CancellationToken;
+use serde_json::json;
 
 static DEFAULT_DETECTOR_SERVICE_LIST: OnceLock<HashMap<&'static str, (DetectorAPIContainer, u64)>> = OnceLock::new();
 static DEFAULT_TRANSLATOR_SERVICE_LIST: OnceLock<HashMap<&'static str, (TranslatorAPIContainer, u64)>> = OnceLock::new();
 
-pub(crate) trait DefaultAPI<T> {
+pub(crate) trait DefaultAPI<T>: Clone {
     fn default_api() -> &'static HashMap<&'static str, (T, u64)>;
 }
 
@@ -61,15 +101,28 @@ impl DefaultAPI<TranslatorAPIContainer> for TranslatorAPIContainer {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum ServiceStatus {
     Ready,
     Retry((u32, Instant)),
     Blocking(u32, Instant),
+    // Probing after a block expired: (block level, consecutive successes)
+    HalfOpen(u32, u32),
+}
+
+impl From<&ServiceStatus> for ServiceState {
+    fn from(status: &ServiceStatus) -> Self {
+        match *status {
+            ServiceStatus::Ready => ServiceState::Ready,
+            ServiceStatus::Retry((retries_left, _)) => ServiceState::Retrying { retries_left },
+            ServiceStatus::Blocking(level, _) => ServiceState::Blocked { level },
+            ServiceStatus::HalfOpen(level, successes) => ServiceState::HalfOpen { level, successes },
+        }
+    }
 }
 
 pub(crate) struct Service<T: 'static> {
-    api: &'static T,
+    api: T,
     status: ServiceStatus,
     last_error: Option<Error>,
     last_error_time: Option<Instant>,
@@ -77,10 +130,16 @@ pub(crate) struct Service<T: 'static> {
     succ_req_times: u64,
     total_req_times: u64,
     consecutive_succ_req_times: u64,
+    consecutive_fail_req_times: u32,
+    limiter: Option<RateLimiter>,
+    priority: usize,
+    latency: Option<Duration>,
+    latencies: LatencyWindow,
+    success_rate: f64,
 }
 
 impl<T> Service<T> {
-    fn new(api: &'static T, weight: u64) -> Service<T> {
+    fn new(api: T, weight: u64, priority: usize) -> Service<T> {
         Self {
             api: api,
             status: ServiceStatus::Ready,
@@ -90,46 +149,95 @@ impl<T> Service<T> {
             succ_req_times: 0,
             total_req_times: 0,
             consecutive_succ_req_times: 0,
+            consecutive_fail_req_times: 0,
+            limiter: None,
+            priority: priority,
+            latency: None,
+            latencies: LatencyWindow::default(),
+            success_rate: 1.0,
+        }
+    }
+
+    // Exponentially weighted moving averages of the latency of successful requests and of the success rate
+    fn record(&mut self, succeeded: bool, elapsed: Duration) {
+        const ALPHA: f64 = 0.2;
+        if succeeded {
+            self.latency = Some(match self.latency {
+                Some(latency) => latency.mul_f64(1.0 - ALPHA) + elapsed.mul_f64(ALPHA),
+                None => elapsed,
+            });
+            self.latencies.push(elapsed);
         }
+        self.success_rate = self.success_rate * (1.0 - ALPHA) + if succeeded { ALPHA } else { 0.0 };
     }
 }
 
 pub(crate) struct Dispatcher<T: 'static> {
     registry: HashMap<String, Service<T>>,
+    policy: Arc<BackoffPolicy>,
+    max_queue_wait: Duration,
+    selection: Selection,
+    cursor: usize,
+    hedging: Option<Hedging>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl<T: DefaultAPI<T>> Dispatcher<T> {
-    pub(crate) fn new(apis: Vec<String>) -> Result<Self> {
+    // The default services, with those registered on the builder added by name, replacing defaults of the same name
+    fn all_apis(custom: Vec<(String, T, u64)>) -> HashMap<String, (T, u64)> {
+        let mut all_apis: HashMap<String, (T, u64)> = T::default_api().iter()
+            .map(|(name, (api, weight))| (name.to_string(), (api.clone(), *weight)))
+            .collect();
+        all_apis.extend(custom.into_iter().map(|(name, api, weight)| (name, (api, weight))));
+        all_apis
+    }
+
+    pub(crate) fn new(apis: Vec<String>, custom: Vec<(String, T, u64)>, policy: BackoffPolicy) -> Result<Self> {
         if 0 == apis.len() {
             return Err(Error::NoTranslatorRegistrationService);
         }
 
-        let all_apis: &HashMap<&str, (T, u64)> = T::default_api();
+        let all_apis = Self::all_apis(custom);
         for name in &apis {
-            if !all_apis.contains_key(&name.as_ref()) {
+            if !all_apis.contains_key(name) {
                 return Err(Error::InvalidServiceName);
             }
         }
 
         let mut dispatcher = Dispatcher {
             registry: HashMap::new(),
+            policy: Arc::new(policy),
+            max_queue_wait: Duration::from_secs(0),
+            selection: Selection::Weighted,
+            cursor: 0,
+            hedging: None,
+            cancellation: None,
         };
 
-        for name in &apis {
-            let api = all_apis.get(name.as_str()).unwrap();
-            dispatcher.registry.insert(name.clone(), Service::new(&api.0, api.1));
+        for (priority, name) in apis.iter().enumerate() {
+            let api = all_apis.get(name).unwrap();
+            dispatcher.registry.insert(name.clone(), Service::new(api.0.clone(), api.1, priority));
         }
 
         Ok(dispatcher)
     }
 
-    pub(crate) fn default() -> Result<Self> {
+    pub(crate) fn default(custom: Vec<(String, T, u64)>, policy: BackoffPolicy) -> Result<Self> {
         let mut dispatcher = Dispatcher {
             registry: HashMap::new(),
+            policy: Arc::new(policy),
+            max_queue_wait: Duration::from_secs(0),
+            selection: Selection::Weighted,
+            cursor: 0,
+            hedging: None,
+            cancellation: None,
         };
 
-        for (name, api) in T::default_api() {
-            dispatcher.registry.insert(name.to_string(), Service::new(&api.0, api.1));
+        // Without an explicit order, heavier services come first
+        let mut apis: Vec<_> = Self::all_apis(custom).into_iter().collect();
+        apis.sort_by(|a, b| b.1.1.cmp(&a.1.1).then(a.0.cmp(&b.0)));
+        for (priority, (name, api)) in apis.into_iter().enumerate() {
+            dispatcher.registry.insert(name, Service::new(api.0, api.1, priority));
         }
 
         Ok(dispatcher)
@@ -169,8 +277,7 @@ impl<T: DefaultAPI<T>> Dispatcher<T> {
     pub(crate) fn dispatch(&mut self, services: &HashMap<String, u64>) -> Result<(String, &mut Service<T>)> {
         let rand_service = |services: &HashMap<String, u64>, total_weight: i64| -> Option<String> {
             let mut rand_num = thread_rng().gen_range(1..=total_weight);
-            let mut iter = services.iter();
-            while let Some((name, weight)) = iter.next() {
+            for (name, weight) in services.iter() {
                 rand_num -= *weight as i64;
                 if rand_num <= 0 {
                     return Some(name.to_string());
@@ -181,16 +288,71 @@ impl<T: DefaultAPI<T>> Dispatcher<T> {
 
         let total_weight = services.iter().fold(0u64, |total, s| { total + s.1 }) as i64;
         if 0 == total_weight {
-            return Err(Error::NoAvailableService(self.calc_max_delay()))
+            return Err(self.unavailable(vec![]))
         }
 
-        let name = rand_service(services, total_weight).unwrap();
+        let name = match self.selection {
+            Selection::Weighted => rand_service(services, total_weight).unwrap(),
+            _ => self.select(services).unwrap(),
+        };
         Ok((name.to_string(), self.registry.get_mut(&name).unwrap()))
     }
 
-    pub(crate) fn handle_result<S, R>(service: &mut Service<S>, result: &Result<R>)
+    // The deterministic selections, among services with a weight, in priority order
+    fn select(&mut self, services: &HashMap<String, u64>) -> Option<String> {
+        let mut available: Vec<(&String, &Service<T>)> = services.iter()
+            .filter(|(_, weight)| **weight > 0)
+            .filter_map(|(name, _)| Some((name, self.registry.get(name)?)))
+            .collect();
+        available.sort_by_key(|(_, service)| service.priority);
+
+        let name = match self.selection {
+            Selection::Weighted | Selection::Priority => available.first()?.0,
+            Selection::RoundRobin => {
+                let index = available.iter()
+                    .position(|(_, service)| service.priority >= self.cursor)
+                    .unwrap_or(0);
+                let (name, service) = available[index];
+                self.cursor = service.priority + 1;
+                name
+            },
+            // Services never tried come first, so that every service gets measured,
+            // and services that only ever failed come last
+            Selection::LeastLatency => available.iter().min_by_key(|(_, service)| match service.latency {
+                None if service.total_req_times == 0 => (0, Duration::ZERO),
+                Some(latency) => (1, latency),
+                None => (2, Duration::ZERO),
+            })?.0,
+            Selection::SuccessRate => available.iter().fold(None, |best: Option<&(&String, &Service<T>)>, candidate| {
+                match best {
+                    Some(best) if best.1.success_rate >= candidate.1.success_rate => Some(best),
+                    _ => Some(candidate),
+                }
+            })?.0,
+        };
+        Some(name.to_string())
+    }
+
+    pub(crate) fn set_selection(&mut self, selection: Selection) {
+        self.selection = selection;
+    }
+
+    pub(crate) fn set_hedging(&mut self, hedging: Option<Hedging>) {
+        self.hedging = hedging;
+    }
+
+    pub(crate) fn set_cancellation(&mut self, cancellation: Option<CancellationToken>) {
+        self.cancellation = cancellation;
+    }
+
+    pub(crate) fn handle_result<S, R>(policy: &BackoffPolicy, service: &mut Service<S>, result: &Result<R>, elapsed: Duration)
         where R: std::fmt::Debug {
 
+        // Failures caused by the request say nothing about the service
+        if !matches!(result, Err(Error::PayloadTooLarge(_) | Error::UnsupportedDirection(..) | Error::LengthLimit(_))) {
+            service.record(result.is_ok(), elapsed);
+        }
+
         match result {
             Ok(_result) => {
                 service.last_error = None;
@@ -198,28 +360,288 @@ impl<T: DefaultAPI<T>> Dispatcher<T> {
                 service.succ_req_times += 1;
                 service.total_req_times += 1;
                 service.consecutive_succ_req_times += 1;
-                service.status = ServiceStatus::Ready;
+                service.consecutive_fail_req_times = 0;
+                service.status = policy.on_success(&service.status);
             },
             Err(e) => {
                 service.last_error = Some(e.clone());
                 service.total_req_times += 1;
                 service.consecutive_succ_req_times = 0;
 
-                if service.last_error_time.is_none() {
-                    service.last_error_time = Some(Instant::now());
+                let now = Instant::now();
+                match e {
+                    // The request is at fault, not the service
+                    Error::PayloadTooLarge(_) | Error::UnsupportedDirection(..) | Error::LengthLimit(_) => {},
+                    Error::RateLimited(Some(delay)) => {
+                        service.status = policy.on_rate_limited(&service.status, now, *delay);
+                    },
+                    // Waiting will not help, block right away instead of retrying
+                    Error::AuthFailure(_) | Error::QuotaExhausted(_) => {
+                        service.consecutive_fail_req_times = service.consecutive_fail_req_times.saturating_add(1);
+                        service.last_error_time.get_or_insert(now);
+                        let status = match service.status {
+                            ServiceStatus::Ready | ServiceStatus::Retry(_) => ServiceStatus::Blocking(0, now),
+                            ref status => status.clone(),
+                        };
+                        service.status = policy.on_failure(&status, now);
+                    },
+                    _ => {
+                        service.consecutive_fail_req_times = service.consecutive_fail_req_times.saturating_add(1);
+                        let first_error = *service.last_error_time.get_or_insert(now);
+                        // A failed probe blocks the service again, whatever the error window
+                        let probing = matches!(service.status, ServiceStatus::HalfOpen(..));
+                        if probing || policy.should_trip(first_error, service.consecutive_fail_req_times, now) {
+                            service.status = policy.on_failure(&service.status, now);
+                        }
+                    },
                 }
-                if Instant::now() - service.last_error_time.unwrap() > Duration::from_secs(1) {
-                    service.status = match service.status {
-                        ServiceStatus::Ready => ServiceStatus::Retry((3, Instant::now() + Duration::from_millis(1_000))),
-                        ServiceStatus::Retry((n, _)) if n > 1 => ServiceStatus::Retry((n - 1, Instant::now() + Duration::from_millis(1_000))),
-                        ServiceStatus::Retry((n, _)) if n == 1 => ServiceStatus::Blocking(1, Instant::now() + Duration::from_millis(3_000)),
-                        ServiceStatus::Blocking(1, _) => ServiceStatus::Blocking(2, Instant::now() + Duration::from_millis(60_000)),
-                        ServiceStatus::Blocking(2, _) => ServiceStatus::Blocking(3, Instant::now() + Duration::from_millis(300_000)),
-                        _ => ServiceStatus::Blocking(3, Instant::now() + Duration::from_millis(3600_000)),
-                    };
+            },
+        }
+    }
+
+    // Handles the result of a request to `name` and records it as an attempt
+    fn attempt<R>(&mut self, name: &str, result: &Result<R>, elapsed: Duration) -> Attempt
+        where R: std::fmt::Debug {
+
+        let policy = self.policy.clone();
+        let service = self.registry.get_mut(name).unwrap();
+        let state_before = ServiceState::from(&service.status);
+        Dispatcher::<T>::handle_result(&policy, service, result, elapsed);
+        Attempt {
+            service: name.to_string(),
+            duration: elapsed,
+            outcome: match result {
+                Ok(_) => Outcome::Succeeded,
+                Err(e) => Outcome::Failed(e.clone()),
+            },
+            state_before: state_before,
+            state_after: ServiceState::from(&service.status),
+        }
+    }
+
+    // A request dropped before it finished, it does not count for the health of the service
+    fn cancelled(&self, name: &str, elapsed: Duration) -> Attempt {
+        let state = ServiceState::from(&self.registry[name].status);
+        Attempt {
+            service: name.to_string(),
+            duration: elapsed,
+            outcome: Outcome::Cancelled,
+            state_before: state,
+            state_after: state,
+        }
+    }
+
+    // `NoAvailableService` with the attempts of the call and the last error of every service
+    fn unavailable(&self, attempts: Vec<Attempt>) -> Error {
+        let mut last_errors: Vec<(String, Error)> = self.registry.iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.last_error.clone()?)))
+            .collect();
+        last_errors.sort_by(|a, b| a.0.cmp(&b.0));
+        Error::NoAvailableService {
+            retry_after: self.calc_max_delay(),
+            attempts: attempts,
+            last_errors: last_errors,
+        }
+    }
+
+    // Replaces the attempts of a `NoAvailableService` returned by `acquire` with those of the whole call
+    fn with_attempts(&self, e: Error, attempts: &mut Vec<Attempt>) -> Error {
+        match e {
+            Error::NoAvailableService { .. } => self.unavailable(std::mem::take(attempts)),
+            e => e,
+        }
+    }
+
+    pub(crate) fn set_rate_limit(&mut self, api: &str, limit: &RateLimit) -> Result<()> {
+        let Some(service) = self.registry.get_mut(api) else {
+            return Err(Error::InvalidServiceName);
+        };
+        service.limiter = Some(RateLimiter::new(limit));
+        Ok(())
+    }
+
+    pub(crate) fn set_max_queue_wait(&mut self, max_queue_wait: Duration) {
+        self.max_queue_wait = max_queue_wait;
+    }
+
+    pub(crate) fn quotas(&mut self) -> Vec<(String, Quota)> {
+        let now = Instant::now();
+        self.registry.iter_mut()
+            .filter_map(|(k, v)| Some((k.to_string(), v.limiter.as_mut()?.remaining(now))))
+            .collect()
+    }
+
+    // Like `dispatch`, but services whose rate limits are used up are skipped. If that leaves none,
+    // waits for the first one to free up, or fails with `RateLimited` if that takes longer than `max_queue_wait`.
+    // Never waits past `deadline`.
+    async fn acquire(&mut self, services: &HashMap<String, u64>, chars: u64, deadline: Option<Instant>) -> Result<String> {
+        loop {
+            let wait = match self.try_acquire(services, chars) {
+                Ok(name) => return Ok(name),
+                Err((_, Some(wait))) => wait,
+                Err((e, None)) => return Err(e),
+            };
+            let max_wait = match deadline {
+                Some(deadline) => self.max_queue_wait.min(deadline.saturating_duration_since(Instant::now())),
+                None => self.max_queue_wait,
+            };
+            if wait > max_wait {
+                return Err(Error::RateLimited(Some(wait)));
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // A single attempt of `acquire`, on failure also returns how long until a rate limited service frees up
+    fn try_acquire(&mut self, services: &HashMap<String, u64>, chars: u64) -> std::result::Result<String, (Error, Option<Duration>)> {
+        let now = Instant::now();
+        let mut wait: Option<Duration> = None;
+        let mut available = services.clone();
+        for (name, weight) in available.iter_mut() {
+            let limiter = self.registry.get_mut(name).and_then(|s| s.limiter.as_mut());
+            let Some(delay) = limiter.and_then(|l| l.wait_time(chars, now)) else {
+                continue;
+            };
+            if *weight > 0 {
+                wait = Some(wait.map_or(delay, |wait| wait.min(delay)));
+            }
+            *weight = 0;
+        }
+
+        match self.dispatch(&available) {
+            Ok((name, service)) => {
+                if let Some(limiter) = service.limiter.as_mut() {
+                    limiter.acquire(chars, now);
                 }
+                Ok(name)
             },
+            Err(e) => Err((e, wait)),
+        }
+    }
+
+    // Sorted by priority
+    pub(crate) fn services(&mut self) -> Vec<ServiceHealth> {
+        let now = Instant::now();
+        let wall_now = SystemTime::now();
+        let mut services: Vec<ServiceHealth> = self.registry.iter()
+            .map(|(name, service)| {
+                let until = match service.status {
+                    ServiceStatus::Retry((_, next)) => Some(next),
+                    ServiceStatus::Blocking(_, end) => Some(end),
+                    _ => None,
+                };
+                ServiceHealth {
+                    name: name.to_string(),
+                    state: ServiceState::from(&service.status),
+                    blocked_until: until.filter(|until| *until > now).map(|until| wall_now + (until - now)),
+                    weight: self.calc_weight(service),
+                    initial_weight: service.init_weight,
+                    priority: service.priority,
+                    requests: service.total_req_times,
+                    successes: service.succ_req_times,
+                    consecutive_successes: service.consecutive_succ_req_times,
+                    consecutive_failures: service.consecutive_fail_req_times,
+                    success_rate: service.success_rate,
+                    latency: service.latency,
+                    latency_histogram: service.latencies.histogram(),
+                    last_error: service.last_error.clone(),
+                    quota: None,
+                }
+            })
+            .collect();
+        for health in services.iter_mut() {
+            let limiter = self.registry.get_mut(&health.name).and_then(|service| service.limiter.as_mut());
+            health.quota = limiter.map(|limiter| limiter.remaining(now));
+        }
+        services.sort_by_key(|health| health.priority);
+        services
+    }
+
+    // The statistics and status of every service, with wall-clock times. The last errors are not saved.
+    pub(crate) fn export_state(&self) -> serde_json::Value {
+        let clock = Clock::now();
+        let services: serde_json::Map<String, serde_json::Value> = self.registry.iter()
+            .map(|(name, service)| {
+                let status = match service.status {
+                    ServiceStatus::Ready => json!({ "state": "ready" }),
+                    ServiceStatus::Retry((retries_left, next)) => json!({ "state": "retry", "retries_left": retries_left, "until": clock.to_unix_millis(next) }),
+                    ServiceStatus::Blocking(level, end) => json!({ "state": "blocking", "level": level, "until": clock.to_unix_millis(end) }),
+                    ServiceStatus::HalfOpen(level, successes) => json!({ "state": "half_open", "level": level, "successes": successes }),
+                };
+                let daily_usage = service.limiter.as_ref().map(|limiter| limiter.daily_usage());
+                (name.to_string(), json!({
+                    "status": status,
+                    "last_error_time": service.last_error_time.map(|time| clock.to_unix_millis(time)),
+                    "succ_req_times": service.succ_req_times,
+                    "total_req_times": service.total_req_times,
+                    "consecutive_succ_req_times": service.consecutive_succ_req_times,
+                    "consecutive_fail_req_times": service.consecutive_fail_req_times,
+                    "latency_millis": service.latency.map(|latency| latency.as_secs_f64() * 1_000.0),
+                    "success_rate": service.success_rate,
+                    "daily_usage": daily_usage.map(|(day, used)| json!({ "day": day, "used": used })),
+                }))
+            })
+            .collect();
+        json!({
+            "version": STATE_VERSION,
+            "saved_at": clock.unix_millis(),
+            "services": services,
+        })
+    }
+
+    // Services that are not registered are skipped, missing or malformed fields keep their current value
+    pub(crate) fn import_state(&mut self, state: &serde_json::Value) -> Result<()> {
+        let Some(services) = state["services"].as_object() else {
+            return Err(Error::JSONParsingError("no `services` in the dispatcher state".to_string()));
+        };
+        let clock = Clock::now();
+        for (name, saved) in services {
+            let Some(service) = self.registry.get_mut(name) else {
+                continue;
+            };
+
+            let status = &saved["status"];
+            let number = |key: &str| status[key].as_u64().map(|n| n as u32);
+            let until = status["until"].as_u64().map(|millis| clock.to_instant(millis));
+            let restored = match status["state"].as_str() {
+                Some("ready") => Some(ServiceStatus::Ready),
+                Some("retry") => number("retries_left").zip(until).map(ServiceStatus::Retry),
+                Some("blocking") => number("level").zip(until).map(|(level, end)| ServiceStatus::Blocking(level, end)),
+                Some("half_open") => number("level").zip(number("successes")).map(|(level, successes)| ServiceStatus::HalfOpen(level, successes)),
+                _ => None,
+            };
+            if let Some(restored) = restored {
+                service.status = restored;
+            }
+
+            // `null` is a service without errors, a missing field keeps what the service has
+            if let Some(time) = saved.get("last_error_time") {
+                service.last_error_time = time.as_u64().map(|millis| clock.to_instant(millis));
+            }
+            if let Some(n) = saved["succ_req_times"].as_u64() {
+                service.succ_req_times = n;
+            }
+            if let Some(n) = saved["total_req_times"].as_u64() {
+                service.total_req_times = n;
+            }
+            if let Some(n) = saved["consecutive_succ_req_times"].as_u64() {
+                service.consecutive_succ_req_times = n;
+            }
+            if let Some(n) = saved["consecutive_fail_req_times"].as_u64() {
+                service.consecutive_fail_req_times = n as u32;
+            }
+            if let Some(millis) = saved["latency_millis"].as_f64() {
+                service.latency = Some(Duration::from_secs_f64(millis.max(0.0) / 1_000.0));
+            }
+            if let Some(rate) = saved["success_rate"].as_f64() {
+                service.success_rate = rate.clamp(0.0, 1.0);
+            }
+            let usage = &saved["daily_usage"];
+            if let (Some(limiter), Some(day), Some(used)) = (service.limiter.as_mut(), usage["day"].as_u64(), usage["used"].as_u64()) {
+                limiter.restore_daily_usage(day, used);
+            }
         }
+        Ok(())
     }
 
     pub(crate) fn last_error(&self, api: &str) -> &Option<Error> {
@@ -235,10 +657,32 @@ impl<T: DefaultAPI<T> + DetectorAPI> Dispatcher<T> {
         let mut services: HashMap<String, u64> = HashMap::new();
         self.registry.iter().for_each(|(k, v)| { services.insert(k.to_string(), self.calc_weight(v)); });
 
+        let chars = text.chars().count() as u64;
+        let cancellation = self.cancellation.clone();
+        let tokens: Vec<&CancellationToken> = cancellation.iter().collect();
+        let mut attempts = vec![];
         loop {
-            let (name, service) = self.dispatch(&mut services)?;
-            let result = service.api.language(request, text.as_ref()).await;
-            Dispatcher::<T>::handle_result(service, &result);
+            let acquired = tokio::select! {
+                biased;
+                interruption = interruption(&tokens, None) => return Err(interruption.into_error(attempts)),
+                acquired = self.acquire(&services, chars, None) => acquired,
+            };
+            let name = acquired.map_err(|e| self.with_attempts(e, &mut attempts))?;
+            let start = Instant::now();
+            let api = self.registry[&name].api.clone();
+            let result = tokio::select! {
+                biased;
+                interruption = interruption(&tokens, None) => Err(interruption),
+                result = api.language(request, text.as_ref()) => Ok(result),
+            };
+            let result = match result {
+                Ok(result) => result,
+                Err(interruption) => {
+                    attempts.push(self.cancelled(&name, start.elapsed()));
+                    return Err(interruption.into_error(attempts));
+                },
+            };
+            attempts.push(self.attempt(&name, &result, start.elapsed()));
             if result.is_ok() {
                 return result;
             };
@@ -247,36 +691,366 @@ impl<T: DefaultAPI<T> + DetectorAPI> Dispatcher<T> {
     }
 }
 
+impl<T: DefaultAPI<T> + DetectorAPI> Dispatcher<T> {
+    pub(crate) async fn dispatch_consensus(&mut self, request: &Request, text: &str) -> Result<Consensus> {
+        let now = Instant::now();
+        let chars = text.chars().count() as u64;
+        let mut candidates: Vec<(String, T, u64)> = vec![];
+        let names: Vec<String> = self.registry.keys().cloned().collect();
+        for name in names {
+            let weight = self.calc_weight(&self.registry[&name]);
+            let service = self.registry.get_mut(&name).unwrap();
+            if weight == 0 {
+                continue;
+            }
+            if let Some(limiter) = service.limiter.as_mut() {
+                if limiter.wait_time(chars, now).is_some() {
+                    continue;
+                }
+                limiter.acquire(chars, now);
+            }
+            candidates.push((name, service.api.clone(), weight));
+        }
+        if candidates.is_empty() {
+            return Err(self.unavailable(vec![]));
+        }
+        // Votes are counted in priority order, which breaks ties
+        candidates.sort_by_key(|(name, _, _)| self.registry[name].priority);
+
+        let cancellation = self.cancellation.clone();
+        let tokens: Vec<&CancellationToken> = cancellation.iter().collect();
+        let start = Instant::now();
+        let results = tokio::select! {
+            biased;
+            interruption = interruption(&tokens, None) => Err(interruption),
+            results = join_all(candidates.iter().map(|(_, api, _)| async move {
+                let start = Instant::now();
+                let result = api.detect(request, text).await;
+                (result, start.elapsed())
+            })) => Ok(results),
+        };
+        let results = match results {
+            Ok(results) => results,
+            Err(interruption) => {
+                let attempts = candidates.iter().map(|(name, _, _)| self.cancelled(name, start.elapsed())).collect();
+                return Err(interruption.into_error(attempts));
+            },
+        };
+
+        let mut votes = vec![];
+        let mut attempts = vec![];
+        let mut errors = vec![];
+        for ((name, _, weight), (result, elapsed)) in candidates.into_iter().zip(results) {
+            attempts.push(self.attempt(&name, &result, elapsed));
+            match result {
+                Ok(detection) => votes.push((name, weight, detection)),
+                Err(e) => errors.push((name, e)),
+            }
+        }
+
+        let Some((language, confidence, disagreement)) = tally(&votes) else {
+            errors.sort_by(|a, b| a.0.cmp(&b.0));
+            return Err(Error::NoAvailableService {
+                retry_after: self.calc_max_delay(),
+                attempts: attempts,
+                last_errors: errors,
+            });
+        };
+        let votes = votes.into_iter().map(|(name, _, detection)| (name, detection)).collect();
+        Ok(Consensus::new(language, confidence, disagreement, votes))
+    }
+}
+
+// Each vote scores `weight * confidence` for its language, the language with the highest score wins,
+// a tie goes to the language voted for first.
+// Returns the winner, its share of the total score, and whether the votes disagree.
+fn tally(votes: &[(String, u64, Detection)]) -> Option<(Language, f64, bool)> {
+    let mut scores: Vec<(Language, f64)> = vec![];
+    for (_, weight, detection) in votes {
+        let score = *weight as f64 * detection.confidence();
+        match scores.iter_mut().find(|(language, _)| *language == detection.language()) {
+            Some((_, total)) => *total += score,
+            None => scores.push((detection.language(), score)),
+        }
+    }
+
+    let total = scores.iter().fold(0f64, |total, (_, score)| total + score);
+    let (language, score) = scores.iter().fold(None, |best: Option<(Language, f64)>, &(language, score)| {
+        match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((language, score)),
+        }
+    })?;
+    let confidence = if total > 0f64 { score / total } else { 0f64 };
+    Some((language, confidence, scores.len() > 1))
+}
+
 impl<T: DefaultAPI<T> + TranslatorAPI> Dispatcher<T> {
-    pub(crate) async fn dispatch_translator(&mut self, request: &Request, text: &str, source: Language, target: Language) -> Result<Translation> {
+    // Services that cannot handle the request are left out instead of being tried and failed over
+    fn capable_services(&self, text: &str, source: Language, target: Language) -> Result<HashMap<String, u64>> {
+        let length = text.chars().count();
         let mut services: HashMap<String, u64> = HashMap::new();
-        self.registry.iter().for_each(|(k, v)| { services.insert(k.to_string(), self.calc_weight(v)); });
+        let mut too_long = false;
+        self.registry.iter().for_each(|(k, v)| {
+            let capabilities = v.api.capabilities();
+            if !capabilities.supports(source, target) {
+                return;
+            }
+            if !capabilities.accepts_length(length) {
+                too_long = true;
+                return;
+            }
+            services.insert(k.to_string(), self.calc_weight(v));
+        });
+
+        if services.is_empty() {
+            return Err(if too_long { Error::LengthLimit(length) } else { Error::UnsupportedDirection(source, target) });
+        }
+        Ok(services)
+    }
+
+    pub(crate) fn capabilities(&self) -> Vec<(String, Capabilities)> {
+        self.registry.iter().map(|(k, v)| (k.to_string(), v.api.capabilities())).collect()
+    }
 
+    pub(crate) async fn dispatch_translator(&mut self, request: &Request, text: &str, source: Language, target: Language,
+        options: &TranslateOptions, deadline: Option<Instant>) -> Result<Translation> {
+
+        self.dispatch_text(request, text, source, target, options, deadline, &mut 0).await
+    }
+
+    // `sent` counts the requests of the whole call, the chunks of a split text share `TranslateOptions::max_attempts`
+    #[allow(clippy::too_many_arguments)]
+    async fn dispatch_text(&mut self, request: &Request, text: &str, source: Language, target: Language,
+        options: &TranslateOptions, deadline: Option<Instant>, sent: &mut usize) -> Result<Translation> {
+
+        if options.pinned().iter().any(|name| !self.registry.contains_key(name)) {
+            return Err(Error::InvalidServiceName);
+        }
+        let mut services = match self.capable_services(text, source, target) {
+            Err(Error::LengthLimit(_)) => return self.dispatch_chunks(request, text, source, target, options, deadline, sent).await,
+            services => services?,
+        };
+        services.retain(|name, _| options.allows(name));
+
+        let chars = text.chars().count() as u64;
+        let cancellation = self.cancellation.clone();
+        let tokens: Vec<&CancellationToken> = cancellation.iter().chain(options.cancellation_token()).collect();
+        let mut attempts = vec![];
         loop {
-            let (name, service) = self.dispatch(&mut services)?;
-            let result = service.api.translate(request, text.as_ref(), source, target).await;
-            Dispatcher::<T>::handle_result(service, &result);
-            if result.is_ok() {
-                return result;
+            if !options.attempts_left(*sent) {
+                return Err(self.unavailable(attempts));
+            }
+            let acquired = tokio::select! {
+                biased;
+                interruption = interruption(&tokens, deadline) => return Err(interruption.into_error(attempts)),
+                acquired = self.acquire(&services, chars, deadline) => acquired,
             };
-            services.remove(&name);
+            let name = acquired.map_err(|e| self.with_attempts(e, &mut attempts))?;
+
+            // Interrupted requests are dropped, which aborts them, and are not held against the service
+            let mut progress = Progress::default();
+            let may_hedge = options.attempts_left(*sent + 1);
+            let done = tokio::select! {
+                biased;
+                interruption = interruption(&tokens, deadline) => Err(interruption),
+                result = self.translate_hedged(&mut progress, may_hedge, &services, name, chars, request, text, source, target, options) => Ok(result),
+            };
+            *sent += progress.attempts.len() + progress.in_flight.len();
+            attempts.append(&mut progress.attempts);
+            let result = match done {
+                Ok(result) => result,
+                Err(interruption) => {
+                    progress.in_flight.iter().for_each(|(name, start)| attempts.push(self.cancelled(name, start.elapsed())));
+                    return Err(interruption.into_error(attempts));
+                },
+            };
+            attempts.iter().filter(|attempt| attempt.error().is_some()).for_each(|attempt| { services.remove(attempt.service()); });
+            match result {
+                Ok(translation) => return Ok(translation.prepend_attempts(attempts)),
+                // Every service would reject it as well, split the text instead of failing over
+                Err(Error::PayloadTooLarge(_)) => {
+                    return match self.dispatch_chunks(request, text, source, target, options, deadline, sent).await {
+                        Ok(translation) => Ok(translation.prepend_attempts(attempts)),
+                        Err(e) => Err(e.prepend_attempts(attempts)),
+                    };
+                },
+                Err(_) => {},
+            }
+        }
+    }
+
+    // Sends the request to `name`. With hedging, if it has not answered within the hedging delay and `may_hedge`,
+    // the request is also sent to another service, the first success wins and the other request is cancelled by
+    // dropping it. Every request is recorded in `progress` as it goes, so that a caller that drops this future
+    // still knows what was sent.
+    #[allow(clippy::too_many_arguments)]
+    async fn translate_hedged(&mut self, progress: &mut Progress, may_hedge: bool, services: &HashMap<String, u64>, name: String, chars: u64,
+        request: &Request, text: &str, source: Language, target: Language, options: &TranslateOptions) -> Result<Translation> {
+
+        let no_options = HashMap::new();
+        let provider_options = |name: &str| options.provider_options(name).unwrap_or(&no_options);
+        progress.in_flight.push((name.clone(), Instant::now()));
+        let primary_api = self.registry[&name].api.clone();
+        let primary = primary_api.translate_with(request, text, source, target, provider_options(&name));
+        tokio::pin!(primary);
+
+        let hedged = match self.hedging.as_ref().filter(|_| may_hedge) {
+            Some(hedging) => {
+                let delay = hedging.delay(&self.registry[&name].latencies);
+                tokio::select! {
+                    result = &mut primary => Some(result),
+                    _ = tokio::time::sleep(delay) => None,
+                }
+            },
+            None => Some((&mut primary).await),
+        };
+        if let Some(result) = hedged {
+            self.landed(progress, &name, &result);
+            return result;
         }
 
+        // Never wait for a rate limit here, the first request is still running
+        let mut others = services.clone();
+        others.remove(&name);
+        let Ok(second_name) = self.try_acquire(&others, chars) else {
+            let result = primary.await;
+            self.landed(progress, &name, &result);
+            return result;
+        };
+        progress.in_flight.push((second_name.clone(), Instant::now()));
+        let second_api = self.registry[&second_name].api.clone();
+        let second = second_api.translate_with(request, text, source, target, provider_options(&second_name));
+        tokio::pin!(second);
+
+        let (result, primary_first) = tokio::select! {
+            result = &mut primary => (result, true),
+            result = &mut second => (result, false),
+        };
+        let (first, later) = match primary_first {
+            true => (name, second_name),
+            false => (second_name, name),
+        };
+        self.landed(progress, &first, &result);
+        if result.is_ok() {
+            let (_, start) = progress.in_flight.remove(0);
+            progress.attempts.push(self.cancelled(&later, start.elapsed()));
+            return result;
+        }
+
+        let result = match primary_first {
+            true => second.await,
+            false => primary.await,
+        };
+        self.landed(progress, &later, &result);
+        result
+    }
+
+    // Records the answer of `name`, which is no longer in flight
+    fn landed(&mut self, progress: &mut Progress, name: &str, result: &Result<Translation>) {
+        let Some(index) = progress.in_flight.iter().position(|(in_flight, _)| in_flight == name) else {
+            return;
+        };
+        let (_, start) = progress.in_flight.remove(index);
+        progress.attempts.push(self.attempt(name, result, start.elapsed()));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn dispatch_chunks(&mut self, request: &Request, text: &str, source: Language, target: Language,
+        options: &TranslateOptions, deadline: Option<Instant>, sent: &mut usize) -> Result<Translation> {
+
+        // The whitespace around the text is put back around the translation, like in a stream
+        let trimmed = text.trim();
+        let prefix = &text[..text.len() - text.trim_start().len()];
+        let suffix = &text[prefix.len() + trimmed.len()..];
+        let Some((head, separator, tail)) = split_text(trimmed) else {
+            return Err(Error::LengthLimit(text.chars().count()));
+        };
+        let head = Box::pin(self.dispatch_text(request, head, source, target, options, deadline, sent)).await?;
+        let tail = Box::pin(self.dispatch_text(request, tail, source, target, options, deadline, sent)).await
+            .map_err(|e| e.prepend_attempts(head.attempts().to_vec()))?;
+        Ok(head.join(separator, tail).surround(prefix, suffix))
+    }
+}
+
+// The requests of a call to a service so far: those that answered, and those still in flight
+#[derive(Default)]
+struct Progress {
+    attempts: Vec<Attempt>,
+    in_flight: Vec<(String, Instant)>,
+}
+
+enum Interruption {
+    Deadline,
+    Cancelled,
+}
+
+impl Interruption {
+    fn into_error(self, attempts: Vec<Attempt>) -> Error {
+        match self {
+            Interruption::Deadline => Error::DeadlineExceeded { attempts: attempts },
+            Interruption::Cancelled => Error::Cancelled { attempts: attempts },
+        }
+    }
+}
+
+// Resolves when a call has to stop, never if it has neither a cancellation token nor a deadline
+async fn interruption(tokens: &[&CancellationToken], deadline: Option<Instant>) -> Interruption {
+    let cancelled = async {
+        match tokens.is_empty() {
+            true => pending().await,
+            false => { select_all(tokens.iter().map(|token| Box::pin(token.cancelled()))).await; },
+        }
+    };
+    let expired = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+            None => pending().await,
+        }
+    };
+    tokio::select! {
+        biased;
+        _ = cancelled => Interruption::Cancelled,
+        _ = expired => Interruption::Deadline,
     }
 }
 
+// Splits a text in two near the middle, preferring line breaks, then sentence ends, then whitespace.
+// Returns the head, the whitespace between both halves and the tail, `text` has no whitespace around it.
+fn split_text(text: &str) -> Option<(&str, &str, &str)> {
+    let middle = text.char_indices().nth(text.chars().count() / 2)?.0;
+    if middle == 0 {
+        return None;
+    }
+
+    let closest = |is_boundary: &dyn Fn(usize, char) -> bool| -> Option<usize> {
+        text.char_indices()
+            .filter(|&(i, c)| i > 0 && is_boundary(i, c))
+            .min_by_key(|&(i, _)| i.abs_diff(middle))
+            .map(|(i, _)| i)
+    };
+    let sentence_end = |i: usize, c: char| {
+        c.is_whitespace() && text[..i].ends_with(['.', '!', '?', '。', '！', '？'])
+    };
+
+    let at = closest(&|_, c| c == '\n')
+        .or_else(|| closest(&sentence_end))
+        .or_else(|| closest(&|_, c| c.is_whitespace()));
+    let Some(at) = at else {
+        return Some((&text[..middle], "", &text[middle..]));
+    };
+
+    let head = text[..at].trim_end();
+    let tail = text[at..].trim_start();
+    Some((head, &text[head.len()..text.len() - tail.len()], tail))
+}
+
 // TODO:
 // Quotas and limits: https://cloud.google.com/translate/quotas?hl=zh-cn
 // 1. The total number of google translate characters per minute does not exceed 6_000_000
 // 2. The total times of google translate per minute does not exceed 60_000
+//    Neither is enforced by default, set a `RateLimit` per service instead
 // 3。Language quota checking is not currently supported
-// 4. 429 => Too Many Requests, // Rate limit
-//    401 => InvalidAPIKey,
-//    402 => BlockedAPIKey,
-//    404 => DailyLimitExceeded,
-//    413 => MaxTextSizeExceeded,
-//    422 => CouldNotTranslate,
-//    501 => TranslationDirectionNotSupported,
 
 
 #[cfg(test)]
@@ -286,4 +1060,293 @@ mod tests {
     fn test_status_machine() {
 
     }
+
+    #[test]
+    fn test_half_open() {
+        use std::time::{Duration, Instant};
+        use super::{Dispatcher, ServiceStatus};
+        use crate::{Error, Translation};
+        use crate::api::TranslatorAPIContainer;
+        use crate::translator::backoff::BackoffPolicy;
+
+        let name = "google.API_MobileGoogleTranslate".to_string();
+        let policy = BackoffPolicy::new().probe_successes(2);
+        let mut dispatcher = Dispatcher::<TranslatorAPIContainer>::new(vec![name.clone()], vec![], policy.clone()).unwrap();
+        let service = dispatcher.registry.get_mut(&name).unwrap();
+        service.status = ServiceStatus::Blocking(1, Instant::now());
+
+        // The successful probe clears the error streak, the failed one must still block under the default error window
+        let succeeded: crate::Result<Translation> = Ok(Translation::new(None, crate::Language::German, "Hallo"));
+        Dispatcher::<TranslatorAPIContainer>::handle_result(&policy, service, &succeeded, Duration::from_millis(10));
+        assert!(matches!(service.status, ServiceStatus::HalfOpen(1, 1)));
+        let failed: crate::Result<Translation> = Err(Error::ReturnedEmptyResult);
+        Dispatcher::<TranslatorAPIContainer>::handle_result(&policy, service, &failed, Duration::from_millis(10));
+        assert!(matches!(service.status, ServiceStatus::Blocking(2, _)));
+    }
+
+    #[test]
+    fn test_selection() {
+        use std::collections::HashMap;
+        use std::time::Duration;
+        use super::Dispatcher;
+        use crate::Selection;
+        use crate::api::TranslatorAPIContainer;
+        use crate::translator::backoff::BackoffPolicy;
+
+        let names = vec![
+            "google.API_GoogleTranslateExtensions".to_string(),
+            "google.API_MobileGoogleTranslate".to_string(),
+            "google.API_GoogleDictionaryChromeExtension".to_string(),
+        ];
+        let mut dispatcher = Dispatcher::<TranslatorAPIContainer>::new(names.clone(), vec![], BackoffPolicy::default()).unwrap();
+        let mut services: HashMap<String, u64> = names.iter().map(|name| (name.clone(), 1)).collect();
+        let pick = |dispatcher: &mut Dispatcher<TranslatorAPIContainer>, services: &HashMap<String, u64>| {
+            dispatcher.dispatch(services).unwrap().0
+        };
+
+        dispatcher.set_selection(Selection::Priority);
+        assert_eq!(pick(&mut dispatcher, &services), names[0]);
+        services.insert(names[0].clone(), 0);
+        assert_eq!(pick(&mut dispatcher, &services), names[1]);
+        services.insert(names[0].clone(), 1);
+
+        dispatcher.set_selection(Selection::RoundRobin);
+        let picked: Vec<String> = (0..4).map(|_| pick(&mut dispatcher, &services)).collect();
+        assert_eq!(picked, vec![names[0].clone(), names[1].clone(), names[2].clone(), names[0].clone()]);
+
+        dispatcher.set_selection(Selection::LeastLatency);
+        for (name, millis) in names.iter().zip([300, 100, 200]) {
+            dispatcher.registry.get_mut(name).unwrap().record(true, Duration::from_millis(millis));
+        }
+        assert_eq!(pick(&mut dispatcher, &services), names[1]);
+
+        // A service that only ever failed has no latency, it comes after the measured ones
+        let service = dispatcher.registry.get_mut(&names[1]).unwrap();
+        (service.latency, service.total_req_times) = (None, 1);
+        assert_eq!(pick(&mut dispatcher, &services), names[2]);
+        // One never tried comes first
+        dispatcher.registry.get_mut(&names[1]).unwrap().total_req_times = 0;
+        assert_eq!(pick(&mut dispatcher, &services), names[1]);
+
+        dispatcher.set_selection(Selection::SuccessRate);
+        dispatcher.registry.get_mut(&names[0]).unwrap().record(false, Duration::from_millis(0));
+        dispatcher.registry.get_mut(&names[1]).unwrap().record(false, Duration::from_millis(0));
+        assert_eq!(pick(&mut dispatcher, &services), names[2]);
+    }
+
+    #[test]
+    fn test_services() {
+        use std::time::Duration;
+        use super::Dispatcher;
+        use crate::{Error, ServiceState, Translation};
+        use crate::api::TranslatorAPIContainer;
+        use crate::translator::backoff::BackoffPolicy;
+
+        let names = vec![
+            "google.API_MobileGoogleTranslate".to_string(),
+            "google.API_GoogleTranslateExtensions".to_string(),
+        ];
+        let policy = BackoffPolicy::new().retries(0, Duration::from_secs(0)).failure_threshold(1);
+        let mut dispatcher = Dispatcher::<TranslatorAPIContainer>::new(names.clone(), vec![], policy.clone()).unwrap();
+        let result: crate::Result<Translation> = Err(Error::ReturnedEmptyResult);
+        Dispatcher::<TranslatorAPIContainer>::handle_result(&policy, dispatcher.registry.get_mut(&names[1]).unwrap(), &result, Duration::from_millis(10));
+
+        let services = dispatcher.services();
+        assert_eq!(services.iter().map(|s| s.name()).collect::<Vec<_>>(), names);
+        assert_eq!(services[0].state(), ServiceState::Ready);
+        assert_eq!(services[0].requests(), 0);
+        assert!(services[0].blocked_until().is_none());
+        assert_eq!(services[0].latency_histogram().iter().map(|(_, n)| n).sum::<u64>(), 0);
+
+        assert_eq!(services[1].state(), ServiceState::Blocked { level: 1 });
+        assert_eq!(services[1].weight(), 0);
+        assert_eq!(services[1].requests(), 1);
+        assert_eq!(services[1].consecutive_failures(), 1);
+        assert!(services[1].blocked_until().is_some());
+        assert!(matches!(services[1].last_error(), Some(Error::ReturnedEmptyResult)));
+        assert!(services[1].success_rate() < 1.0);
+    }
+
+    #[test]
+    fn test_state() {
+        use std::time::Duration;
+        use super::{Dispatcher, ServiceStatus};
+        use crate::{Error, RateLimit, Translation};
+        use crate::api::TranslatorAPIContainer;
+        use crate::translator::backoff::BackoffPolicy;
+
+        let names = vec![
+            "google.API_MobileGoogleTranslate".to_string(),
+            "google.API_GoogleTranslateExtensions".to_string(),
+        ];
+        let policy = BackoffPolicy::new().retries(0, Duration::from_secs(0)).failure_threshold(1);
+        let mut dispatcher = Dispatcher::<TranslatorAPIContainer>::new(names.clone(), vec![], policy.clone()).unwrap();
+        dispatcher.set_rate_limit(&names[0], &RateLimit::new().daily_chars(1_000)).unwrap();
+        dispatcher.registry.get_mut(&names[0]).unwrap().limiter.as_mut().unwrap().acquire(100, std::time::Instant::now());
+        let failed: crate::Result<Translation> = Err(Error::ReturnedEmptyResult);
+        Dispatcher::<TranslatorAPIContainer>::handle_result(&policy, dispatcher.registry.get_mut(&names[1]).unwrap(), &failed, Duration::from_millis(10));
+
+        // Through a string, like a state file
+        let state: serde_json::Value = serde_json::from_str(&dispatcher.export_state().to_string()).unwrap();
+        let mut restored = Dispatcher::<TranslatorAPIContainer>::new(names.clone(), vec![], policy.clone()).unwrap();
+        restored.set_rate_limit(&names[0], &RateLimit::new().daily_chars(1_000)).unwrap();
+        restored.import_state(&state).unwrap();
+
+        let saved = &dispatcher.registry[&names[1]];
+        let service = &restored.registry[&names[1]];
+        let (ServiceStatus::Blocking(1, saved_end), ServiceStatus::Blocking(1, end)) = (&saved.status, &service.status) else {
+            panic!("expected a restored block");
+        };
+        assert!(saved_end.max(end).duration_since(*saved_end.min(end)) < Duration::from_millis(5));
+        assert_eq!(service.total_req_times, 1);
+        assert_eq!(service.consecutive_fail_req_times, 1);
+        assert_eq!(service.success_rate, saved.success_rate);
+        assert!(service.last_error_time.is_some());
+
+        let partial = serde_json::json!({ "services": { &names[1]: { "total_req_times": 2 } } });
+        restored.import_state(&partial).unwrap();
+        assert_eq!(restored.registry[&names[1]].total_req_times, 2);
+        assert!(restored.registry[&names[1]].last_error_time.is_some());
+        restored.import_state(&serde_json::json!({ "services": { &names[1]: { "last_error_time": null } } })).unwrap();
+        assert!(restored.registry[&names[1]].last_error_time.is_none());
+        assert_eq!(restored.quotas().iter().find(|(name, _)| *name == names[0]).unwrap().1.daily_chars(), Some(900));
+
+        assert!(restored.import_state(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_attempts() {
+        use std::collections::HashMap;
+        use std::time::Duration;
+        use super::Dispatcher;
+        use crate::{Error, Outcome, ServiceState, Translation};
+        use crate::api::TranslatorAPIContainer;
+        use crate::translator::backoff::BackoffPolicy;
+
+        let names = vec![
+            "google.API_MobileGoogleTranslate".to_string(),
+            "google.API_GoogleTranslateExtensions".to_string(),
+        ];
+        let policy = BackoffPolicy::new().retries(0, Duration::from_secs(0)).failure_threshold(1);
+        let mut dispatcher = Dispatcher::<TranslatorAPIContainer>::new(names.clone(), vec![], policy).unwrap();
+
+        let failed: crate::Result<Translation> = Err(Error::ReturnedEmptyResult);
+        let mut attempts = vec![dispatcher.attempt(&names[1], &failed, Duration::from_millis(10))];
+        assert_eq!(attempts[0].service(), names[1]);
+        assert_eq!(attempts[0].outcome(), &Outcome::Failed(Error::ReturnedEmptyResult));
+        assert_eq!(attempts[0].state_before(), ServiceState::Ready);
+        assert_eq!(attempts[0].state_after(), ServiceState::Blocked { level: 1 });
+        attempts.push(dispatcher.cancelled(&names[0], Duration::from_millis(20)));
+        assert_eq!(attempts[1].outcome(), &Outcome::Cancelled);
+
+        let services: HashMap<String, u64> = names.iter().map(|name| (name.clone(), 0)).collect();
+        let e = dispatcher.dispatch(&services).err().unwrap();
+        let Error::NoAvailableService { retry_after, attempts: none, last_errors } = dispatcher.with_attempts(e, &mut attempts) else {
+            panic!("expected NoAvailableService");
+        };
+        assert!(retry_after > Duration::from_secs(0));
+        assert_eq!(none.len(), 2);
+        assert!(attempts.is_empty());
+        assert_eq!(last_errors, vec![(names[1].clone(), Error::ReturnedEmptyResult)]);
+
+        assert_eq!(dispatcher.with_attempts(Error::InvalidServiceName, &mut vec![]), Error::InvalidServiceName);
+    }
+
+    #[tokio::test]
+    async fn test_capable_services() {
+        use crate::{Language, Result, Selection, Strategy, Translation, Translator};
+        use crate::api::{Capabilities, Request, TranslatorAPI};
+        use async_trait::async_trait;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Limited(usize, Arc<AtomicUsize>);
+
+        #[async_trait]
+        impl TranslatorAPI for Limited {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+
+            fn capabilities(&self) -> Capabilities {
+                Capabilities::new().max_length(self.0)
+            }
+        }
+
+        let (short, long) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Mix(vec!["short".to_string(), "long".to_string()]))
+            .selection(Selection::Priority)
+            .service("short", Limited(10, short.clone()), 100)
+            .service("long", Limited(1_000, long.clone()), 100)
+            .build()
+            .unwrap();
+
+        // The text is too long for the first service, which is skipped without a request or a failure
+        let translation = translator.translate("Hello world, how are you?", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.attempts().len(), 1);
+        assert_eq!(translation.attempts()[0].service(), "long");
+        assert_eq!((short.load(Ordering::SeqCst), long.load(Ordering::SeqCst)), (0, 1));
+        assert!(translator.last_error("short").is_none());
+
+        let translation = translator.translate("Hello", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.attempts()[0].service(), "short");
+        assert_eq!((short.load(Ordering::SeqCst), long.load(Ordering::SeqCst)), (1, 1));
+
+        // A split text keeps the whitespace around it
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("short".to_string()))
+            .service("short", Limited(10, short.clone()), 100)
+            .build()
+            .unwrap();
+        let translation = translator.translate("  Hello world, how are you?\n", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.result(), "  HELLO WORLD, HOW ARE YOU?\n");
+    }
+
+    #[test]
+    fn test_split_text() {
+        use super::split_text;
+
+        assert_eq!(split_text(""), None);
+        assert_eq!(split_text("a"), None);
+        assert_eq!(split_text("ab"), Some(("a", "", "b")));
+        assert_eq!(split_text("你好世界"), Some(("你好", "", "世界")));
+        assert_eq!(split_text("one two three four"), Some(("one two", " ", "three four")));
+        assert_eq!(split_text("First one. Second sentence here"), Some(("First one.", " ", "Second sentence here")));
+        assert_eq!(split_text("a b c\n\nd e f g h i j"), Some(("a b c", "\n\n", "d e f g h i j")));
+    }
+
+    #[test]
+    fn test_tally() {
+        use super::tally;
+        use crate::Language;
+        use crate::api::Detection;
+
+        assert!(tally(&[]).is_none());
+        assert_eq!(Detection::new(Language::English, f64::NAN).confidence(), 0.0);
+
+        let (language, confidence, disagreement) = tally(&[
+            ("a".to_string(), 100, Detection::new(Language::English, 1.0)),
+        ]).unwrap();
+        assert_eq!(language, Language::English);
+        assert_eq!(confidence, 1.0);
+        assert!(!disagreement);
+
+        let (language, confidence, disagreement) = tally(&[
+            ("a".to_string(), 100, Detection::new(Language::German, 0.5)),
+            ("b".to_string(), 100, Detection::new(Language::Dutch, 0.9)),
+            ("c".to_string(), 20, Detection::new(Language::German, 1.0)),
+        ]).unwrap();
+        assert_eq!(language, Language::Dutch);
+        assert!((confidence - 90.0 / 160.0).abs() < 1e-9);
+        assert!(disagreement);
+
+        let (language, confidence, _) = tally(&[
+            ("a".to_string(), 100, Detection::new(Language::French, 1.0)),
+            ("b".to_string(), 100, Detection::new(Language::German, 1.0)),
+        ]).unwrap();
+        assert_eq!(language, Language::French);
+        assert_eq!(confidence, 0.5);
+    }
 }
\ No newline at end of file
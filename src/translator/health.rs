@@ -0,0 +1,109 @@
+use crate::Error;
+use crate::translator::rate_limit::Quota;
+
+use std::time::{
+    Duration,
+    SystemTime
+};
+
+/// Where a service is in its backoff, see `BackoffPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceState {
+    Ready,
+    /// Failed recently, `retries_left` short retries remain before it is blocked.
+    Retrying { retries_left: u32 },
+    /// Taken out of rotation, the n-th consecutive block is `level`.
+    Blocked { level: u32 },
+    /// A block expired and the service is being tried again.
+    HalfOpen { level: u32, successes: u32 },
+}
+
+/// A snapshot of the health and statistics of a service, returned by `Translator::services`.
+#[derive(Debug, Clone)]
+pub struct ServiceHealth {
+    pub(crate) name: String,
+    pub(crate) state: ServiceState,
+    pub(crate) blocked_until: Option<SystemTime>,
+    pub(crate) weight: u64,
+    pub(crate) initial_weight: u64,
+    pub(crate) priority: usize,
+    pub(crate) requests: u64,
+    pub(crate) successes: u64,
+    pub(crate) consecutive_successes: u64,
+    pub(crate) consecutive_failures: u32,
+    pub(crate) success_rate: f64,
+    pub(crate) latency: Option<Duration>,
+    pub(crate) latency_histogram: Vec<(Duration, u64)>,
+    pub(crate) last_error: Option<Error>,
+    pub(crate) quota: Option<Quota>,
+}
+
+impl ServiceHealth {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> ServiceState {
+        self.state
+    }
+
+    /// Until when the service is skipped, `None` if it can be used right now.
+    pub fn blocked_until(&self) -> Option<SystemTime> {
+        self.blocked_until
+    }
+
+    /// The weight used to pick a service, `0` while it is skipped.
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    pub fn initial_weight(&self) -> u64 {
+        self.initial_weight
+    }
+
+    /// Position in the priority order, `0` comes first.
+    pub fn priority(&self) -> usize {
+        self.priority
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    pub fn successes(&self) -> u64 {
+        self.successes
+    }
+
+    pub fn consecutive_successes(&self) -> u64 {
+        self.consecutive_successes
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Moving average of recent requests, `1.0` before the first request.
+    pub fn success_rate(&self) -> f64 {
+        self.success_rate
+    }
+
+    /// Moving average latency of successful requests.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Recent successful requests counted per bucket, each bucket given by its upper bound.
+    pub fn latency_histogram(&self) -> &[(Duration, u64)] {
+        &self.latency_histogram
+    }
+
+    /// The error of the last request, `None` if it succeeded.
+    pub fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+
+    /// What is left of the `RateLimit` of the service, if it has one.
+    pub fn quota(&self) -> Option<Quota> {
+        self.quota
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// Latency samples kept per service, and how many are needed before the percentile is trusted
+const SAMPLES: usize = 100;
+const MIN_SAMPLES: usize = 10;
+// Upper bounds of the buckets of `LatencyWindow::histogram` in milliseconds, the last bucket is unbounded
+const HISTOGRAM_BUCKETS: [u64; 8] = [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Sends a request to a second service when the first one is slow, the first answer wins and the other is cancelled.
+///
+/// A service is slow once it takes longer than the `percentile` of its recent latencies. Until enough
+/// latencies are measured, `initial_delay` is used instead.
+#[derive(Debug, Clone)]
+pub struct Hedging {
+    percentile: f64,
+    initial_delay: Duration,
+    min_delay: Duration,
+}
+
+impl Default for Hedging {
+    fn default() -> Self {
+        Self {
+            percentile: 0.95,
+            initial_delay: Duration::from_millis(1_000),
+            min_delay: Duration::from_millis(50),
+        }
+    }
+}
+
+impl Hedging {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `0.0..=1.0`, e.g. `0.95` hedges the slowest 5% of requests.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Never hedge sooner than this, so that fast services do not double the load.
+    pub fn min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    pub(crate) fn delay(&self, latencies: &LatencyWindow) -> Duration {
+        latencies.percentile(self.percentile).unwrap_or(self.initial_delay).max(self.min_delay)
+    }
+}
+
+// The latencies of the last successful requests of a service
+#[derive(Debug, Default)]
+pub(crate) struct LatencyWindow {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    pub(crate) fn push(&mut self, latency: Duration) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    pub(crate) fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let index = ((sorted.len() - 1) as f64 * percentile).round() as usize;
+        Some(sorted[index])
+    }
+
+    pub(crate) fn histogram(&self) -> Vec<(Duration, u64)> {
+        let mut histogram: Vec<(Duration, u64)> = HISTOGRAM_BUCKETS.iter()
+            .map(|millis| Duration::from_millis(*millis))
+            .chain([Duration::MAX])
+            .map(|bound| (bound, 0))
+            .collect();
+        for latency in &self.samples {
+            if let Some((_, count)) = histogram.iter_mut().find(|(bound, _)| latency <= bound) {
+                *count += 1;
+            }
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn test_delay() {
+        use super::{Hedging, LatencyWindow};
+
+        let hedging = Hedging::new().percentile(0.9).initial_delay(Duration::from_millis(500)).min_delay(Duration::from_millis(20));
+        let mut latencies = LatencyWindow::default();
+        for millis in 1..10 {
+            latencies.push(Duration::from_millis(millis * 10));
+        }
+        assert_eq!(hedging.delay(&latencies), Duration::from_millis(500));
+
+        latencies.push(Duration::from_millis(100));
+        assert_eq!(hedging.delay(&latencies), Duration::from_millis(90));
+
+        // Only the last samples count
+        for _ in 0..100 {
+            latencies.push(Duration::from_millis(1));
+        }
+        assert_eq!(latencies.percentile(1.0), Some(Duration::from_millis(1)));
+        assert_eq!(hedging.delay(&latencies), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_histogram() {
+        use super::LatencyWindow;
+
+        let mut latencies = LatencyWindow::default();
+        for millis in [10, 50, 51, 20_000] {
+            latencies.push(Duration::from_millis(millis));
+        }
+        let histogram = latencies.histogram();
+        assert_eq!(histogram.len(), 9);
+        assert_eq!(histogram[0], (Duration::from_millis(50), 2));
+        assert_eq!(histogram[1], (Duration::from_millis(100), 1));
+        assert_eq!(histogram[8], (Duration::MAX, 1));
+    }
+}
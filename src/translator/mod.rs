@@ -1,8 +1,36 @@
 mod translator;
 mod dispatcher;
+mod backoff;
+mod rate_limit;
+mod hedging;
+mod health;
+mod attempt;
+mod options;
+mod state;
+mod stream;
+
+pub use self::backoff::{
+    Backoff,
+    BackoffPolicy
+};
+pub use self::attempt::{
+    Attempt,
+    Outcome
+};
+pub use self::hedging::Hedging;
+pub use self::options::TranslateOptions;
+pub use self::health::{
+    ServiceHealth,
+    ServiceState
+};
+pub use self::rate_limit::{
+    Quota,
+    RateLimit
+};
 
 pub use self::translator::{
     Strategy,
+    Selection,
     TranslatorType,
     TranslatorBuilder,
     Translator,
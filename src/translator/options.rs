@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Options of a single `Translator::translate_with` call, on top of those of the `Translator`.
+#[derive(Debug, Clone, Default)]
+pub struct TranslateOptions {
+    deadline: Option<Duration>,
+    max_attempts: Option<usize>,
+    only: Vec<String>,
+    exclude: Vec<String>,
+    provider_options: HashMap<String, HashMap<String, String>>,
+    cancellation: Option<CancellationToken>,
+    segment_size: Option<usize>,
+}
+
+impl TranslateOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails with `Error::DeadlineExceeded` if the call, including every failover, takes longer.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops failing over after this many requests, counting the hedged ones and those of every chunk of a split text.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts.max(1));
+        self
+    }
+
+    /// Only use these services, each must be part of the strategy.
+    pub fn only(mut self, names: &[&str]) -> Self {
+        self.only = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Never use this service.
+    pub fn exclude(mut self, name: &str) -> Self {
+        self.exclude.push(name.to_string());
+        self
+    }
+
+    /// An option passed to the service `name` only, see `TranslatorAPI::translate_with`.
+    pub fn provider_option(mut self, name: &str, key: &str, value: &str) -> Self {
+        self.provider_options.entry(name.to_string()).or_default().insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Aborts the call with `Error::Cancelled` once `token` is cancelled, in-flight requests are dropped.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// The longest segment, in characters, that `Translator::translate_stream` and `Translator::translate_reader` translate at once.
+    pub fn segment_size(mut self, chars: usize) -> Self {
+        self.segment_size = Some(chars.max(1));
+        self
+    }
+
+    pub(crate) fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    pub(crate) fn segment_chars(&self) -> Option<usize> {
+        self.segment_size
+    }
+
+    pub(crate) fn deadline_duration(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    pub(crate) fn attempts_left(&self, attempts: usize) -> bool {
+        self.max_attempts.is_none_or(|max| attempts < max)
+    }
+
+    pub(crate) fn allows(&self, name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|only| only == name)) && !self.exclude.iter().any(|exclude| exclude == name)
+    }
+
+    pub(crate) fn pinned(&self) -> &[String] {
+        &self.only
+    }
+
+    pub(crate) fn provider_options(&self, name: &str) -> Option<&HashMap<String, String>> {
+        self.provider_options.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_options() {
+        use super::TranslateOptions;
+
+        let options = TranslateOptions::new();
+        assert!(options.allows("a"));
+        assert!(options.attempts_left(usize::MAX - 1));
+
+        let options = TranslateOptions::new()
+            .only(&["a", "b"])
+            .exclude("b")
+            .max_attempts(2)
+            .provider_option("a", "key", "value");
+        assert!(options.allows("a"));
+        assert!(!options.allows("b"));
+        assert!(!options.allows("c"));
+        assert!(options.attempts_left(1));
+        assert!(!options.attempts_left(2));
+        assert_eq!(options.provider_options("a").unwrap()["key"], "value");
+        assert!(options.provider_options("b").is_none());
+    }
+}
@@ -0,0 +1,239 @@
+use std::time::{
+    Duration,
+    Instant,
+    SystemTime,
+    UNIX_EPOCH
+};
+
+const MINUTE: Duration = Duration::from_secs(60);
+const DAY_SECS: u64 = 86_400;
+
+/// Client-side limits of a single service, requests that would exceed them are routed elsewhere or delayed.
+///
+/// A request uses its quota when it is sent, the provider most likely charges it even if it fails or is
+/// dropped before it answers. A call waiting for its turn uses none until its request is sent.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    chars_per_minute: Option<u64>,
+    requests_per_minute: Option<u64>,
+    daily_chars: Option<u64>,
+}
+
+impl RateLimit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chars_per_minute(mut self, chars: u64) -> Self {
+        self.chars_per_minute = Some(chars.max(1));
+        self
+    }
+
+    pub fn requests_per_minute(mut self, requests: u64) -> Self {
+        self.requests_per_minute = Some(requests.max(1));
+        self
+    }
+
+    /// Characters per UTC calendar day.
+    pub fn daily_chars(mut self, chars: u64) -> Self {
+        self.daily_chars = Some(chars);
+        self
+    }
+}
+
+/// What is left of the limits of a service, `None` where no limit is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    chars_per_minute: Option<u64>,
+    requests_per_minute: Option<u64>,
+    daily_chars: Option<u64>,
+}
+
+impl Quota {
+    /// Characters that can be sent right now.
+    pub fn chars_per_minute(&self) -> Option<u64> {
+        self.chars_per_minute
+    }
+
+    /// Requests that can be sent right now.
+    pub fn requests_per_minute(&self) -> Option<u64> {
+        self.requests_per_minute
+    }
+
+    /// Characters left for today.
+    pub fn daily_chars(&self) -> Option<u64> {
+        self.daily_chars
+    }
+}
+
+// Refills continuously at `capacity` tokens per minute
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u64, now: Instant) -> Self {
+        Self { capacity: capacity as f64, tokens: capacity as f64, updated: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / MINUTE.as_secs_f64()).min(self.capacity);
+        self.updated = now;
+    }
+
+    // A cost above the capacity is allowed once the bucket is full, otherwise it could never pass
+    fn wait_time(&mut self, cost: u64, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        let needed = (cost as f64).min(self.capacity);
+        if self.tokens >= needed {
+            return None;
+        }
+        Some(Duration::from_secs_f64((needed - self.tokens) * MINUTE.as_secs_f64() / self.capacity))
+    }
+
+    fn take(&mut self, cost: u64, now: Instant) {
+        self.refill(now);
+        self.tokens -= cost as f64;
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    chars: Option<TokenBucket>,
+    requests: Option<TokenBucket>,
+    daily_chars: Option<u64>,
+    day: u64,
+    used_today: u64,
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: &RateLimit) -> Self {
+        let now = Instant::now();
+        Self {
+            chars: limit.chars_per_minute.map(|c| TokenBucket::new(c, now)),
+            requests: limit.requests_per_minute.map(|r| TokenBucket::new(r, now)),
+            daily_chars: limit.daily_chars,
+            day: unix_secs() / DAY_SECS,
+            used_today: 0,
+        }
+    }
+
+    fn roll_day(&mut self, unix_secs: u64) {
+        if unix_secs / DAY_SECS != self.day {
+            self.day = unix_secs / DAY_SECS;
+            self.used_today = 0;
+        }
+    }
+
+    /// How long to wait before `chars` characters can be sent, `None` if they can be sent now.
+    pub(crate) fn wait_time(&mut self, chars: u64, now: Instant) -> Option<Duration> {
+        self.wait_time_at(chars, now, unix_secs())
+    }
+
+    fn wait_time_at(&mut self, chars: u64, now: Instant, unix_secs: u64) -> Option<Duration> {
+        self.roll_day(unix_secs);
+        if let Some(daily) = self.daily_chars {
+            if self.used_today + chars > daily {
+                return Some(Duration::from_secs(DAY_SECS - unix_secs % DAY_SECS));
+            }
+        }
+        let chars = self.chars.as_mut().and_then(|bucket| bucket.wait_time(chars, now));
+        let requests = self.requests.as_mut().and_then(|bucket| bucket.wait_time(1, now));
+        chars.max(requests)
+    }
+
+    pub(crate) fn acquire(&mut self, chars: u64, now: Instant) {
+        self.roll_day(unix_secs());
+        self.used_today += chars;
+        if let Some(bucket) = self.chars.as_mut() {
+            bucket.take(chars, now);
+        }
+        if let Some(bucket) = self.requests.as_mut() {
+            bucket.take(1, now);
+        }
+    }
+
+    // The day, in days since the Unix epoch, and the characters used on it
+    pub(crate) fn daily_usage(&self) -> (u64, u64) {
+        (self.day, self.used_today)
+    }
+
+    // Usage saved on another day is outdated
+    pub(crate) fn restore_daily_usage(&mut self, day: u64, used: u64) {
+        self.roll_day(unix_secs());
+        if day == self.day {
+            self.used_today = used;
+        }
+    }
+
+    pub(crate) fn remaining(&mut self, now: Instant) -> Quota {
+        self.roll_day(unix_secs());
+        let remaining = |bucket: &mut Option<TokenBucket>| bucket.as_mut().map(|bucket| {
+            bucket.refill(now);
+            bucket.tokens.max(0.0) as u64
+        });
+        Quota {
+            chars_per_minute: remaining(&mut self.chars),
+            requests_per_minute: remaining(&mut self.requests),
+            daily_chars: self.daily_chars.map(|daily| daily.saturating_sub(self.used_today)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{
+        Duration,
+        Instant
+    };
+
+    #[test]
+    fn test_token_bucket() {
+        use super::{RateLimit, RateLimiter};
+
+        let mut limiter = RateLimiter::new(&RateLimit::new().chars_per_minute(600).requests_per_minute(2));
+        let now = Instant::now();
+
+        assert_eq!(limiter.wait_time(600, now), None);
+        limiter.acquire(600, now);
+        assert_eq!(limiter.remaining(now).chars_per_minute(), Some(0));
+        assert_eq!(limiter.remaining(now).requests_per_minute(), Some(1));
+
+        // 10 characters per second
+        assert_eq!(limiter.wait_time(100, now), Some(Duration::from_secs(10)));
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.wait_time(100, later), None);
+        limiter.acquire(100, later);
+
+        // A third of a request refilled in 10 seconds, the rest takes 20 more
+        let wait = limiter.wait_time(1, later).unwrap();
+        assert!((wait.as_secs_f64() - 20.0).abs() < 1e-6);
+
+        // Longer than the capacity, allowed once the bucket is full
+        let full = now + Duration::from_secs(120);
+        assert_eq!(limiter.wait_time(1_000, full), None);
+    }
+
+    #[test]
+    fn test_daily_budget() {
+        use super::{RateLimit, RateLimiter, DAY_SECS};
+
+        let mut limiter = RateLimiter::new(&RateLimit::new().daily_chars(100));
+        let now = Instant::now();
+        let today = limiter.day * DAY_SECS;
+
+        assert_eq!(limiter.wait_time_at(100, now, today), None);
+        limiter.used_today = 100;
+        assert_eq!(limiter.wait_time_at(1, now, today + DAY_SECS - 60), Some(Duration::from_secs(60)));
+        assert_eq!(limiter.wait_time_at(1, now, today + DAY_SECS), None);
+        assert_eq!(limiter.used_today, 0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    Error,
+    Result
+};
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{
+    Duration,
+    Instant,
+    SystemTime,
+    UNIX_EPOCH
+};
+
+// Bumped when the layout of the state file changes, files of other versions are ignored
+pub(crate) const STATE_VERSION: u64 = 1;
+
+// `Instant`s cannot be saved, they are converted to milliseconds since the Unix epoch through the current time
+pub(crate) struct Clock {
+    now: Instant,
+    wall_now: SystemTime,
+}
+
+impl Clock {
+    pub(crate) fn now() -> Self {
+        Self { now: Instant::now(), wall_now: SystemTime::now() }
+    }
+
+    pub(crate) fn to_unix_millis(&self, instant: Instant) -> u64 {
+        let wall = match instant.checked_duration_since(self.now) {
+            Some(ahead) => self.wall_now + ahead,
+            None => self.wall_now - self.now.duration_since(instant),
+        };
+        wall.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+    }
+
+    // Times too far in the past for an `Instant` are clamped to now
+    pub(crate) fn to_instant(&self, millis: u64) -> Instant {
+        let wall = UNIX_EPOCH + Duration::from_millis(millis);
+        match wall.duration_since(self.wall_now) {
+            Ok(ahead) => self.now + ahead,
+            Err(e) => self.now.checked_sub(e.duration()).unwrap_or(self.now),
+        }
+    }
+
+    pub(crate) fn unix_millis(&self) -> u64 {
+        self.to_unix_millis(self.now)
+    }
+}
+
+/// Reads a state file, `None` if there is none yet.
+pub(crate) fn read(path: &Path) -> Result<Option<serde_json::Value>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::IOError(e.to_string())),
+    };
+    let state = serde_json::from_str::<serde_json::Value>(&content).map_err(|e| Error::JSONParsingError(e.to_string()))?;
+    if state["version"].as_u64() != Some(STATE_VERSION) {
+        return Ok(None);
+    }
+    Ok(Some(state))
+}
+
+// Written to a temporary file first, so that a crash never leaves a truncated state behind
+pub(crate) fn write(path: &Path, state: &serde_json::Value) -> Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, state.to_string()).map_err(|e| Error::IOError(e.to_string()))?;
+    fs::rename(&temp, path).map_err(|e| Error::IOError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn test_clock() {
+        use super::Clock;
+
+        let clock = Clock::now();
+        let later = clock.now + Duration::from_secs(90);
+        let millis = clock.to_unix_millis(later);
+        assert_eq!(millis, clock.unix_millis() + 90_000);
+        // Saved with millisecond precision
+        let restored = clock.to_instant(millis);
+        assert!(restored <= later && later - restored < Duration::from_millis(1));
+
+        let earlier = clock.to_instant(clock.unix_millis() - 5_000);
+        assert!(clock.now - earlier > Duration::from_millis(4_999));
+    }
+}
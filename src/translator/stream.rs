@@ -0,0 +1,238 @@
+use crate::Result;
+use crate::Error;
+use crate::Language;
+use crate::Translation;
+use crate::Translator;
+use crate::translator::options::TranslateOptions;
+
+use std::pin::Pin;
+use futures::stream::{
+    self,
+    Stream,
+    StreamExt
+};
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt
+};
+
+const DEFAULT_SEGMENT_SIZE: usize = 1_000;
+// How many bytes are read from an `AsyncRead` at once
+const READ_SIZE: usize = 8 * 1024;
+
+struct State<'a> {
+    translator: &'a mut Translator,
+    input: Pin<Box<dyn Stream<Item = Result<String>> + 'a>>,
+    buffer: String,
+    finished: bool,
+    source: Language,
+    target: Language,
+    options: TranslateOptions,
+}
+
+impl State<'_> {
+    async fn translate(&mut self, segment: String) -> Result<Translation> {
+        let text = segment.trim();
+        if text.is_empty() {
+            return Ok(Translation::new((self.source != Language::Auto).then_some(self.source), self.target, &segment));
+        }
+        let prefix = &segment[..segment.len() - segment.trim_start().len()];
+        let suffix = &segment[prefix.len() + text.len()..];
+        let translation = self.translator.translate_with(text, self.source, self.target, &self.options).await?;
+        Ok(translation.surround(prefix, suffix))
+    }
+}
+
+// Translates the segments one at a time: the input is only read when the buffer holds less than a segment
+pub(crate) fn translate<'a>(
+    translator: &'a mut Translator,
+    input: impl Stream<Item = Result<String>> + 'a,
+    source: Language,
+    target: Language,
+    options: TranslateOptions
+) -> impl Stream<Item = Result<Translation>> + 'a {
+    let state = State {
+        translator: translator,
+        input: Box::pin(input),
+        buffer: String::new(),
+        finished: false,
+        source: source,
+        target: target,
+        options: options,
+    };
+    stream::unfold(state, |mut state| async move {
+        let size = state.options.segment_chars().unwrap_or(DEFAULT_SEGMENT_SIZE);
+        loop {
+            if let Some(segment) = next_segment(&mut state.buffer, size, state.finished) {
+                let translation = state.translate(segment).await;
+                return Some((translation, state));
+            }
+            if state.finished {
+                return None;
+            }
+            match state.input.next().await {
+                Some(Ok(text)) => state.buffer.push_str(&text),
+                Some(Err(e)) => {
+                    // Nothing can be read after a failed read
+                    state.finished = true;
+                    state.buffer.clear();
+                    return Some((Err(e), state));
+                },
+                None => state.finished = true,
+            }
+        }
+    })
+}
+
+// The text of `reader` in blocks, a character split between two reads is kept for the next one
+pub(crate) fn read<'a>(reader: impl AsyncRead + 'a) -> impl Stream<Item = Result<String>> + 'a {
+    stream::unfold((Box::pin(reader), Vec::new(), false), |(mut reader, mut pending, done)| async move {
+        if done {
+            return None;
+        }
+        let mut block = [0; READ_SIZE];
+        let read = match reader.read(&mut block).await {
+            Ok(read) => read,
+            Err(e) => return Some((Err(Error::IOError(e.to_string())), (reader, pending, true))),
+        };
+        if read == 0 {
+            return match pending.is_empty() {
+                true => None,
+                false => Some((Err(Error::IOError("stream did not contain valid UTF-8".to_string())), (reader, pending, true))),
+            };
+        }
+        pending.extend_from_slice(&block[..read]);
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Some((Err(Error::IOError(e.to_string())), (reader, pending, true))),
+        };
+        let rest = pending.split_off(valid);
+        let text = String::from_utf8(std::mem::replace(&mut pending, rest)).expect("checked above");
+        Some((Ok(text), (reader, pending, false)))
+    })
+}
+
+// Takes the next segment out of `buffer`, once it holds more than `size` characters or the input is finished.
+// Segments are cut after the last line break, sentence end or space within `size` characters, and keep the whitespace that follows.
+fn next_segment(buffer: &mut String, size: usize, finished: bool) -> Option<String> {
+    if buffer.is_empty() {
+        return None;
+    }
+    let limit = match buffer.char_indices().nth(size) {
+        Some((limit, _)) => limit,
+        None if finished => return Some(std::mem::take(buffer)),
+        None => return None,
+    };
+
+    // A boundary right after the limit is fine, the whitespace is not translated
+    let end = limit + buffer[limit..].chars().next().map_or(0, char::len_utf8);
+    let window = &buffer[..end];
+    let last = |is_boundary: &dyn Fn(usize, char) -> bool| -> Option<usize> {
+        window.char_indices()
+            .filter(|&(i, c)| i > 0 && is_boundary(i, c))
+            .last()
+            .map(|(i, c)| i + c.len_utf8())
+    };
+    let sentence_end = |i: usize, c: char| {
+        c.is_whitespace() && window[..i].ends_with(['.', '!', '?', '。', '！', '？'])
+    };
+    let mut at = last(&|_, c| c == '\n')
+        .or_else(|| last(&sentence_end))
+        .or_else(|| last(&|_, c| c.is_whitespace()))
+        .unwrap_or(limit);
+    // Whitespace right after the cut stays with the segment before it
+    at += buffer[at..].len() - buffer[at..].trim_start().len();
+
+    let rest = buffer.split_off(at);
+    Some(std::mem::replace(buffer, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_next_segment() {
+        use super::next_segment;
+
+        let mut buffer = "Hello world. How are you?\nFine".to_string();
+        assert_eq!(next_segment(&mut buffer, 100, false), None);
+        assert_eq!(next_segment(&mut buffer, 20, false).as_deref(), Some("Hello world. "));
+        assert_eq!(next_segment(&mut buffer, 20, false), None);
+        assert_eq!(next_segment(&mut buffer, 15, false).as_deref(), Some("How are you?\n"));
+        assert_eq!(next_segment(&mut buffer, 15, true).as_deref(), Some("Fine"));
+        assert_eq!(next_segment(&mut buffer, 15, true), None);
+
+        // Without any boundary, the text is cut at the limit
+        let mut buffer = "ééééé".to_string();
+        assert_eq!(next_segment(&mut buffer, 2, false).as_deref(), Some("éé"));
+        assert_eq!(buffer, "ééé");
+    }
+
+    #[tokio::test]
+    async fn test_read() {
+        use futures::StreamExt;
+        use super::read;
+
+        // A character split between two reads
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let text = "Grüße";
+        let split = text.find('ü').unwrap() + 1;
+        let writes = async move {
+            use tokio::io::AsyncWriteExt;
+            writer.write_all(&text.as_bytes()[..split]).await.unwrap();
+            tokio::task::yield_now().await;
+            writer.write_all(&text.as_bytes()[split..]).await.unwrap();
+        };
+        let (_, blocks) = tokio::join!(writes, read(reader).collect::<Vec<_>>());
+        let blocks: Vec<String> = blocks.into_iter().map(|block| block.unwrap()).collect();
+        assert_eq!(blocks.concat(), text);
+
+        let blocks: Vec<_> = read(&[0x66, 0xff][..]).collect().await;
+        assert!(blocks.last().unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_translate_stream() {
+        use crate::{Language, Result, Strategy, TranslateOptions, Translator, Translation};
+        use crate::api::{Request, TranslatorAPI};
+        use async_trait::async_trait;
+        use futures::StreamExt;
+
+        struct Upper;
+
+        #[async_trait]
+        impl TranslatorAPI for Upper {
+            async fn translate(&self, _request: &Request, text: &str, _source: Language, target: Language) -> Result<Translation> {
+                Ok(Translation::new(Some(Language::English), target, &text.to_uppercase()))
+            }
+        }
+
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("upper".to_string()))
+            .service("upper", Upper, 100)
+            .build()
+            .unwrap();
+
+        let input = futures::stream::iter(["First line.\n", "Second", " line.\n\n", "Third"].map(String::from));
+        let options = TranslateOptions::new().segment_size(12);
+        let results: Vec<String> = translator.translate_stream(input, Language::English, Language::German, &options)
+            .map(|translation| translation.unwrap().into_result())
+            .collect()
+            .await;
+        assert_eq!(results, ["FIRST LINE.\n", "SECOND LINE.\n\n", "THIRD"]);
+
+        let text = "Line one\nLine two\n".repeat(100);
+        let results: Vec<String> = translator.translate_reader(text.as_bytes(), Language::English, Language::German, &TranslateOptions::default())
+            .map(|translation| translation.unwrap().into_result())
+            .collect()
+            .await;
+        assert!(results.len() > 1);
+        assert_eq!(results.concat(), text.to_uppercase());
+
+        // Errors are yielded in place of the segment
+        let input = futures::stream::iter(["Hello".to_string()]);
+        let mut results = std::pin::pin!(translator.translate_stream(input, Language::German, Language::German, &TranslateOptions::default()));
+        assert!(results.next().await.unwrap().is_err());
+        assert!(results.next().await.is_none());
+    }
+}
@@ -4,18 +4,49 @@ use crate::Result;
 use crate::Error;
 use crate::Language;
 use crate::Translation;
+use crate::Consensus;
+use crate::Capabilities;
 use crate::translator::dispatcher::Dispatcher;
+use crate::translator::backoff::BackoffPolicy;
+use crate::translator::hedging::Hedging;
+use crate::translator::health::ServiceHealth;
+use crate::translator::state;
+use crate::translator::stream;
+use crate::translator::options::TranslateOptions;
+use crate::translator::rate_limit::{
+    Quota,
+    RateLimit
+};
 use crate::api::{
+    HttpConfig,
+    Transport,
+    DetectorAPI,
+    TranslatorAPI,
     DetectorAPIContainer,
     TranslatorAPIContainer,
     Request
 };
 
 use std::rc::Rc;
-use std::cell::RefCell;
-use std::time::Duration;
+use std::sync::Arc;
+use std::path::{
+    Path,
+    PathBuf
+};
+use tokio::io::AsyncRead;
+use futures::stream::{
+    Stream,
+    StreamExt
+};
+use std::time::{
+    Duration,
+    Instant
+};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 const DEFAULT_TIMEOUT_MILLIS: u64 = 30_000;
+const DEFAULT_MAX_QUEUE_WAIT_MILLIS: u64 = 60_000;
 
 #[derive(Debug)]
 pub enum Strategy {
@@ -24,6 +55,25 @@ pub enum Strategy {
     Mix(Vec<String>),
 }
 
+/// How a service is picked among the available ones of a `Strategy`.
+///
+/// Services that are blocked, rate limited or cannot handle the request are never picked,
+/// the order of a `Strategy::Mix` is the priority order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// Randomly, in proportion to the weight of each service.
+    Weighted,
+    /// The first service in priority order, later ones are only used as fallbacks.
+    Priority,
+    /// Each service in priority order in turn.
+    RoundRobin,
+    /// The service with the lowest moving average latency. Services not tried yet come first,
+    /// services that never succeeded come last.
+    LeastLatency,
+    /// The service with the highest recent success rate.
+    SuccessRate,
+}
+
 #[derive(Debug)]
 pub enum TranslatorType {
     Text,
@@ -31,10 +81,19 @@ pub enum TranslatorType {
     SubTitleFile,
 }
 
-#[derive(Debug)]
 pub(crate) struct Config {
     strategy: Strategy,
     timeout: Duration,
+    backoff: BackoffPolicy,
+    rate_limits: Vec<(String, RateLimit)>,
+    max_queue_wait: Duration,
+    selection: Selection,
+    cancellation: Option<CancellationToken>,
+    state_file: Option<PathBuf>,
+    http: HttpConfig,
+    transport: Option<Arc<dyn Transport>>,
+    translators: Vec<(String, TranslatorAPIContainer, u64)>,
+    detectors: Vec<(String, DetectorAPIContainer, u64)>,
 }
 
 pub struct Detector {
@@ -57,18 +116,42 @@ impl DetectorBuilder {
             config: Config {
                 strategy: Strategy::Default,
                 timeout: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
+                backoff: BackoffPolicy::default(),
+                rate_limits: vec![],
+                max_queue_wait: Duration::from_millis(DEFAULT_MAX_QUEUE_WAIT_MILLIS),
+                selection: Selection::Weighted,
+                cancellation: None,
+                state_file: None,
+                http: HttpConfig::default(),
+                transport: None,
+                translators: vec![],
+                detectors: vec![],
             }
         }
     }
 
     pub fn build(self) -> Result<Detector> {
-        let dispatcher: Dispatcher<DetectorAPIContainer> = match &self.config.strategy {
-            Strategy::Default => Dispatcher::default()?,
-            Strategy::Single(name) => Dispatcher::new(vec![name.clone()])?,
-            Strategy::Mix(names) => Dispatcher::new(names.clone())?,
+        let services = self.config.detectors.clone();
+        let mut dispatcher: Dispatcher<DetectorAPIContainer> = match &self.config.strategy {
+            Strategy::Default => Dispatcher::default(services, self.config.backoff.clone())?,
+            Strategy::Single(name) => Dispatcher::new(vec![name.clone()], services, self.config.backoff.clone())?,
+            Strategy::Mix(names) => Dispatcher::new(names.clone(), services, self.config.backoff.clone())?,
         };
 
-        let request = Request::new(self.config.timeout)?;
+        for (name, limit) in &self.config.rate_limits {
+            dispatcher.set_rate_limit(name, limit)?;
+        }
+        dispatcher.set_max_queue_wait(self.config.max_queue_wait);
+        dispatcher.set_selection(self.config.selection.clone());
+        dispatcher.set_cancellation(self.config.cancellation.clone());
+        if let Some(state) = self.config.state_file.as_deref().map(state::read).transpose()?.flatten() {
+            dispatcher.import_state(&state)?;
+        }
+
+        let request = match &self.config.transport {
+            Some(transport) => Request::from_arc(transport.clone()),
+            None => Request::with_config(self.config.timeout, &self.config.http)?,
+        };
 
         Ok(Detector {
             config: self.config,
@@ -86,6 +169,62 @@ impl DetectorBuilder {
         self.config.timeout = timeout;
         self
     }
+
+    /// Proxies, user agents, headers and TLS of the HTTP client, see `HttpConfig`.
+    pub fn http(mut self, http: HttpConfig) -> Self {
+        self.config.http = http;
+        self
+    }
+
+    /// Sends every request through `transport` instead of the built-in client,
+    /// `timeout` and `http` are then left to the transport.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// How failing services are retried, blocked and brought back, see `BackoffPolicy`.
+    pub fn backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.config.backoff = backoff;
+        self
+    }
+
+    /// Client-side limits for the service `name`, which must be part of the strategy.
+    pub fn rate_limit(mut self, name: &str, limit: RateLimit) -> Self {
+        self.config.rate_limits.push((name.to_string(), limit));
+        self
+    }
+
+    /// How long to wait when every service is at its rate limit, before failing with `Error::RateLimited`.
+    pub fn max_queue_wait(mut self, max_queue_wait: Duration) -> Self {
+        self.config.max_queue_wait = max_queue_wait;
+        self
+    }
+
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.config.selection = selection;
+        self
+    }
+
+    /// Restores the state saved by `save_state`, so that services blocked before a restart stay blocked.
+    /// A missing file is not an error.
+    pub fn load_state(mut self, path: impl AsRef<Path>) -> Self {
+        self.config.state_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Aborts every call with `Error::Cancelled` once `token` is cancelled, in-flight requests are dropped.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.config.cancellation = Some(token);
+        self
+    }
+
+    /// Registers a service of your own under `name`, to be used in the strategy like the built-in ones.
+    /// It replaces the built-in service of the same name, if any.
+    pub fn service(mut self, name: &str, api: impl DetectorAPI + 'static, weight: u64) -> Self {
+        self.config.detectors.push((name.to_string(), DetectorAPIContainer::new(api), weight));
+        self
+    }
 }
 
 impl Detector {
@@ -93,13 +232,36 @@ impl Detector {
         DetectorBuilder::new()
     }
 
+    /// The requests sent are only reported on failure, see `Error`, `consensus` reports every answer.
     pub async fn language(&mut self, text: &str) -> Result<Language> {
         self.dispatcher.dispatch_detector(&self.request, text).await
     }
+
+    /// Asks every available service in parallel and combines the answers by weighted voting.
+    pub async fn consensus(&mut self, text: &str) -> Result<Consensus> {
+        self.dispatcher.dispatch_consensus(&self.request, text).await
+    }
+
+    /// Remaining quota of every service with a `RateLimit`.
+    pub fn quotas(&mut self) -> Vec<(String, Quota)> {
+        self.dispatcher.quotas()
+    }
+
+    /// Health and statistics of every service, in priority order.
+    pub fn services(&mut self) -> Vec<ServiceHealth> {
+        self.dispatcher.services()
+    }
+
+    /// Saves the health of every service as JSON, to be restored with the builder's `load_state`.
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        state::write(path.as_ref(), &self.dispatcher.export_state())
+    }
 }
 
 pub struct TranslatorBuilder {
     config: Config,
+    // Only translations are hedged, a detection is cheap enough to fail over instead
+    hedging: Option<Hedging>,
 }
 
 impl TranslatorBuilder {
@@ -112,18 +274,44 @@ impl TranslatorBuilder {
             config: Config {
                 strategy: Strategy::Default,
                 timeout: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
-            }
+                backoff: BackoffPolicy::default(),
+                rate_limits: vec![],
+                max_queue_wait: Duration::from_millis(DEFAULT_MAX_QUEUE_WAIT_MILLIS),
+                selection: Selection::Weighted,
+                cancellation: None,
+                state_file: None,
+                http: HttpConfig::default(),
+                transport: None,
+                translators: vec![],
+                detectors: vec![],
+            },
+            hedging: None,
         }
     }
 
     pub fn build(self) -> Result<Translator> {
-        let dispatcher: Dispatcher<TranslatorAPIContainer> = match &self.config.strategy {
-            Strategy::Default => Dispatcher::default()?,
-            Strategy::Single(name) => Dispatcher::new(vec![name.clone()])?,
-            Strategy::Mix(names) => Dispatcher::new(names.clone())?,
+        let services = self.config.translators.clone();
+        let mut dispatcher: Dispatcher<TranslatorAPIContainer> = match &self.config.strategy {
+            Strategy::Default => Dispatcher::default(services, self.config.backoff.clone())?,
+            Strategy::Single(name) => Dispatcher::new(vec![name.clone()], services, self.config.backoff.clone())?,
+            Strategy::Mix(names) => Dispatcher::new(names.clone(), services, self.config.backoff.clone())?,
         };
 
-        let request = Request::new(self.config.timeout)?;
+        for (name, limit) in &self.config.rate_limits {
+            dispatcher.set_rate_limit(name, limit)?;
+        }
+        dispatcher.set_max_queue_wait(self.config.max_queue_wait);
+        dispatcher.set_selection(self.config.selection.clone());
+        dispatcher.set_cancellation(self.config.cancellation.clone());
+        if let Some(state) = self.config.state_file.as_deref().map(state::read).transpose()?.flatten() {
+            dispatcher.import_state(&state)?;
+        }
+        dispatcher.set_hedging(self.hedging);
+
+        let request = match &self.config.transport {
+            Some(transport) => Request::from_arc(transport.clone()),
+            None => Request::with_config(self.config.timeout, &self.config.http)?,
+        };
 
         Ok(Translator {
             config: self.config,
@@ -141,6 +329,69 @@ impl TranslatorBuilder {
         self.config.timeout = timeout;
         self
     }
+
+    /// Proxies, user agents, headers and TLS of the HTTP client, see `HttpConfig`.
+    pub fn http(mut self, http: HttpConfig) -> Self {
+        self.config.http = http;
+        self
+    }
+
+    /// Sends every request through `transport` instead of the built-in client,
+    /// `timeout` and `http` are then left to the transport.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// How failing services are retried, blocked and brought back, see `BackoffPolicy`.
+    pub fn backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.config.backoff = backoff;
+        self
+    }
+
+    /// Client-side limits for the service `name`, which must be part of the strategy.
+    pub fn rate_limit(mut self, name: &str, limit: RateLimit) -> Self {
+        self.config.rate_limits.push((name.to_string(), limit));
+        self
+    }
+
+    /// How long to wait when every service is at its rate limit, before failing with `Error::RateLimited`.
+    pub fn max_queue_wait(mut self, max_queue_wait: Duration) -> Self {
+        self.config.max_queue_wait = max_queue_wait;
+        self
+    }
+
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.config.selection = selection;
+        self
+    }
+
+    /// Restores the state saved by `save_state`, so that services blocked before a restart stay blocked.
+    /// A missing file is not an error.
+    pub fn load_state(mut self, path: impl AsRef<Path>) -> Self {
+        self.config.state_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sends slow requests to a second service as well, see `Hedging`.
+    pub fn hedging(mut self, hedging: Hedging) -> Self {
+        self.hedging = Some(hedging);
+        self
+    }
+
+    /// Aborts every call with `Error::Cancelled` once `token` is cancelled, in-flight requests are dropped.
+    /// A single call is cancelled with `TranslateOptions::cancellation` instead.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.config.cancellation = Some(token);
+        self
+    }
+
+    /// Registers a service of your own under `name`, to be used in the strategy like the built-in ones.
+    /// It replaces the built-in service of the same name, if any.
+    pub fn service(mut self, name: &str, api: impl TranslatorAPI + 'static, weight: u64) -> Self {
+        self.config.translators.push((name.to_string(), TranslatorAPIContainer::new(api), weight));
+        self
+    }
 }
 
 pub struct Translator {
@@ -155,44 +406,112 @@ impl Translator {
     }
 
     pub async fn translate(&mut self, text: &str, source: Language, target: Language) -> Result<Translation> {
+        self.translate_with(text, source, target, &TranslateOptions::default()).await
+    }
+
+    /// Like `translate`, with a deadline, a limit on failovers or a choice of services for this call only.
+    pub async fn translate_with(&mut self, text: &str, source: Language, target: Language, options: &TranslateOptions) -> Result<Translation> {
         if target == Language::Auto {
             return Err(Error::TargetLanguageIsAuto);
         }
         if target == source {
             return Err(Error::TargetEqualToSource);
         }
-        self.dispatcher.dispatch_translator(&self.request, text, source, target).await
+        // A deadline too far away for an `Instant` is no deadline
+        let deadline = options.deadline_duration().and_then(|deadline| Instant::now().checked_add(deadline));
+        self.dispatcher.dispatch_translator(&self.request, text, source, target, options, deadline).await
+    }
+
+    /// Translates text as it arrives, yielding the translation of each segment in order.
+    ///
+    /// The input is cut at line breaks, sentence ends or spaces into segments of at most `TranslateOptions::segment_size`
+    /// characters, 1000 by default. More input is only read once the previous translation was taken from the stream,
+    /// so memory stays bounded whatever the length of the input. Joined together, the results keep the whitespace of the input.
+    pub fn translate_stream<'a>(
+        &'a mut self,
+        input: impl Stream<Item = String> + 'a,
+        source: Language,
+        target: Language,
+        options: &TranslateOptions
+    ) -> impl Stream<Item = Result<Translation>> + 'a {
+        stream::translate(self, input.map(Ok), source, target, options.clone())
+    }
+
+    /// Like `translate_stream`, with the UTF-8 text of `reader`.
+    pub fn translate_reader<'a>(
+        &'a mut self,
+        reader: impl AsyncRead + 'a,
+        source: Language,
+        target: Language,
+        options: &TranslateOptions
+    ) -> impl Stream<Item = Result<Translation>> + 'a {
+        stream::translate(self, stream::read(reader), source, target, options.clone())
     }
 
     pub fn last_error(&self, api: &str) -> Option<Error> {
         self.dispatcher.last_error(api).clone()
     }
+
+    /// What each registered service can translate, see `Capabilities`.
+    pub fn capabilities(&self) -> Vec<(String, Capabilities)> {
+        self.dispatcher.capabilities()
+    }
+
+    /// Remaining quota of every service with a `RateLimit`.
+    pub fn quotas(&mut self) -> Vec<(String, Quota)> {
+        self.dispatcher.quotas()
+    }
+
+    /// Health and statistics of every service, in priority order.
+    pub fn services(&mut self) -> Vec<ServiceHealth> {
+        self.dispatcher.services()
+    }
+
+    /// Saves the health of every service as JSON, to be restored with the builder's `load_state`.
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        state::write(path.as_ref(), &self.dispatcher.export_state())
+    }
 }
 
+// The borrow lasts for the whole call, so concurrent calls on one thread wait for each other instead of panicking
 thread_local! {
-    static DEFAULT_DETECTOR: Rc<RefCell<Detector>> = Rc::new(RefCell::new(Detector::builder().build().unwrap()));
-    static DEFAULT_TRANSLATOR: Rc<RefCell<Translator>> = Rc::new(RefCell::new(Translator::builder().build().unwrap()));
+    static DEFAULT_DETECTOR: Rc<Mutex<Detector>> = Rc::new(Mutex::new(Detector::builder().build().unwrap()));
+    static DEFAULT_TRANSLATOR: Rc<Mutex<Translator>> = Rc::new(Mutex::new(Translator::builder().build().unwrap()));
 }
 
 pub async fn translate(text: &str, source: Language, target: Language) -> Result<Translation> {
     let translator = DEFAULT_TRANSLATOR.with(|r| { r.clone() });
-    let translator = &mut *(*translator).borrow_mut();
+    let mut translator = translator.lock().await;
     translator.translate(text, source, target).await
 }
 
 pub async fn language(text: &str) -> Result<Language> {
     let detector = DEFAULT_DETECTOR.with(|r| { r.clone() });
-    let detector = &mut *(*detector).borrow_mut();
+    let mut detector = detector.lock().await;
+    let detector = &mut *detector;
     detector.dispatcher.dispatch_detector(&detector.request, text).await
 }
 
 
 #[cfg(test)]
 mod tests {
+    #[tokio::test]
+    async fn test_concurrent_defaults() {
+        use crate::Language;
+        use super::{language, translate};
+
+        // Both calls share the thread's default translator, the second one waits for the first
+        let _ = tokio::join!(
+            translate("Hello", Language::English, Language::German),
+            translate("World", Language::English, Language::German),
+            language("Hallo"),
+            language("Welt")
+        );
+    }
+
     #[test]
     fn test_translator_builder() {
         use crate::Error;
-        use crate::Language;
         use crate::Strategy;
         use crate::Translator;
 
@@ -231,11 +550,229 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_translate_with() {
+        use std::time::Duration;
+        use crate::{CancellationToken, Error, Language, Strategy, TranslateOptions, Translator};
+
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("google.API_MobileGoogleTranslate".to_string()))
+            .build()
+            .unwrap();
+
+        let options = TranslateOptions::new().only(&["google.API_GoogleTranslateExtensions"]);
+        assert!(matches!(translator.translate_with("Hello", Language::English, Language::German, &options).await,
+            Err(Error::InvalidServiceName)));
+
+        let options = TranslateOptions::new().exclude("google.API_MobileGoogleTranslate");
+        let Err(Error::NoAvailableService { attempts, .. }) = translator.translate_with("Hello", Language::English, Language::German, &options).await else {
+            panic!("expected NoAvailableService");
+        };
+        assert!(attempts.is_empty());
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = TranslateOptions::new().cancellation(token);
+        assert!(matches!(translator.translate_with("Hello", Language::English, Language::German, &options).await,
+            Err(Error::Cancelled { .. })));
+
+        let options = TranslateOptions::new().deadline(Duration::from_secs(0));
+        assert!(matches!(translator.translate_with("Hello", Language::English, Language::German, &options).await,
+            Err(Error::DeadlineExceeded { .. })));
+
+        let options = TranslateOptions::new().deadline(Duration::MAX).exclude("google.API_MobileGoogleTranslate");
+        assert!(matches!(translator.translate_with("Hello", Language::English, Language::German, &options).await,
+            Err(Error::NoAvailableService { .. })));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_cancellation() {
+        use std::time::Duration;
+        use crate::{CancellationToken, Detector, Error, Hedging, Language, Outcome, RateLimit, Selection, Strategy, TranslateOptions, Translator};
+        use crate::api::{MockDetector, MockTranslator};
+
+        // Cancels `token` while the requests are in flight
+        let cancel_soon = |token: &CancellationToken| {
+            let token = token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                token.cancel();
+            });
+        };
+        let cancelled = |attempts: &[crate::Attempt]| attempts.iter().all(|attempt| attempt.outcome() == &Outcome::Cancelled);
+        let slow = || MockTranslator::new().latency(Duration::from_secs(5));
+
+        // Plain `translate`, with the hedged request recorded as well
+        let token = CancellationToken::new();
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Mix(vec!["a".to_string(), "b".to_string()]))
+            .selection(Selection::Priority)
+            .hedging(Hedging::new().initial_delay(Duration::from_millis(20)).min_delay(Duration::from_millis(10)))
+            .cancellation(token.clone())
+            .service("a", slow(), 100)
+            .service("b", slow(), 100)
+            .build()
+            .unwrap();
+        cancel_soon(&token);
+        let Err(Error::Cancelled { attempts }) = translator.translate("Hello", Language::English, Language::German).await else {
+            panic!("expected Cancelled");
+        };
+        assert_eq!(attempts.iter().map(|attempt| attempt.service()).collect::<Vec<_>>(), ["a", "b"]);
+        assert!(cancelled(&attempts));
+
+        let token = CancellationToken::new();
+        let mut detector = Detector::builder()
+            .strategy(Strategy::Mix(vec!["a".to_string(), "b".to_string()]))
+            .cancellation(token.clone())
+            .service("a", MockDetector::new().latency(Duration::from_secs(5)), 100)
+            .service("b", MockDetector::new().latency(Duration::from_secs(5)), 100)
+            .build()
+            .unwrap();
+        cancel_soon(&token);
+        let Err(Error::Cancelled { attempts }) = detector.language("Hallo").await else {
+            panic!("expected Cancelled");
+        };
+        assert_eq!(attempts.len(), 1);
+        assert!(cancelled(&attempts));
+
+        // The token stays cancelled, so is every later call
+        let Err(Error::Cancelled { attempts }) = detector.consensus("Hallo").await else {
+            panic!("expected Cancelled");
+        };
+        assert_eq!(attempts.len(), 2);
+        assert!(cancelled(&attempts));
+
+        // A request dropped at the deadline was sent, it keeps using its quota
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("slow".to_string()))
+            .rate_limit("slow", RateLimit::new().daily_chars(100))
+            .service("slow", slow(), 100)
+            .build()
+            .unwrap();
+        let options = TranslateOptions::new().deadline(Duration::from_millis(50));
+        let result = translator.translate_with("Hello", Language::English, Language::German, &options).await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded { .. })));
+        assert_eq!(translator.quotas()[0].1.daily_chars(), Some(95));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_hedging() {
+        use std::time::{Duration, Instant};
+        use crate::{Hedging, Language, Outcome, Selection, Strategy, Translator};
+        use crate::api::MockTranslator;
+
+        let slow = MockTranslator::new().latency(Duration::from_millis(500)).translation("SLOW");
+        let fast = MockTranslator::new().latency(Duration::from_millis(10)).translation("FAST");
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Mix(vec!["slow".to_string(), "fast".to_string()]))
+            .selection(Selection::Priority)
+            .hedging(Hedging::new().initial_delay(Duration::from_millis(20)).min_delay(Duration::from_millis(10)))
+            .service("slow", slow.clone(), 100)
+            .service("fast", fast.clone(), 100)
+            .build()
+            .unwrap();
+
+        // The hedged request answers first, the slow one is dropped without waiting for it
+        let start = Instant::now();
+        let translation = translator.translate("Hello", Language::English, Language::German).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert_eq!(translation.result(), "FAST");
+        assert_eq!((slow.calls(), fast.calls()), (1, 1));
+
+        let attempts = translation.attempts();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!((attempts[0].service(), attempts[0].outcome()), ("fast", &Outcome::Succeeded));
+        assert_eq!((attempts[1].service(), attempts[1].outcome()), ("slow", &Outcome::Cancelled));
+        // The dropped request is not held against the service
+        assert!(translator.last_error("slow").is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn test_attempt_budget() {
+        use std::time::Duration;
+        use crate::{Capabilities, Error, Hedging, Language, Outcome, Selection, Strategy, TranslateOptions, Translator};
+        use crate::api::MockTranslator;
+
+        let hedged = |a: &MockTranslator, b: &MockTranslator| Translator::builder()
+            .strategy(Strategy::Mix(vec!["a".to_string(), "b".to_string()]))
+            .selection(Selection::Priority)
+            .hedging(Hedging::new().initial_delay(Duration::from_millis(20)).min_delay(Duration::from_millis(10)))
+            .service("a", a.clone(), 100)
+            .service("b", b.clone(), 100)
+            .build()
+            .unwrap();
+
+        // The hedged request counts against the limit, so it is not sent
+        let (a, b) = (MockTranslator::new().latency(Duration::from_millis(200)).translation("A"), MockTranslator::new());
+        let options = TranslateOptions::new().max_attempts(1);
+        let translation = hedged(&a, &b).translate_with("Hello", Language::English, Language::German, &options).await.unwrap();
+        assert_eq!(translation.result(), "A");
+        assert_eq!(translation.attempts().len(), 1);
+        assert_eq!(b.calls(), 0);
+
+        // A failure before the call ran out of time is kept, with the hedged request still in flight
+        let (a, b) = (MockTranslator::new().latency(Duration::from_millis(50)).status(503), MockTranslator::new().latency(Duration::from_secs(5)));
+        let options = TranslateOptions::new().deadline(Duration::from_millis(300));
+        let Err(Error::DeadlineExceeded { attempts }) = hedged(&a, &b).translate_with("Hello", Language::English, Language::German, &options).await else {
+            panic!("expected DeadlineExceeded");
+        };
+        assert_eq!(attempts.len(), 2);
+        assert_eq!((attempts[0].service(), attempts[1].service()), ("a", "b"));
+        assert!(matches!(attempts[0].outcome(), Outcome::Failed(_)));
+        assert_eq!(attempts[1].outcome(), &Outcome::Cancelled);
+
+        // The chunks of a split text share the limit
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("short".to_string()))
+            .service("short", MockTranslator::new().capabilities(Capabilities::new().max_length(15)), 100)
+            .build()
+            .unwrap();
+        let text = "Hello world, how are you?";
+        let options = TranslateOptions::new().max_attempts(1);
+        let Err(Error::NoAvailableService { attempts, .. }) = translator.translate_with(text, Language::English, Language::German, &options).await else {
+            panic!("expected NoAvailableService");
+        };
+        // The failure of the second chunk keeps the request of the first
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].outcome(), &Outcome::Succeeded);
+        let options = TranslateOptions::new().max_attempts(2);
+        assert_eq!(translator.translate_with(text, Language::English, Language::German, &options).await.unwrap().attempts().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_transport() {
+        use crate::{HttpRequest, HttpResponse, Language, Result, Strategy, Transport, Translator};
+        use async_trait::async_trait;
+
+        struct Offline;
+
+        #[async_trait]
+        impl Transport for Offline {
+            async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+                assert!(request.url().starts_with("https://translate.google.com/m?"));
+                Ok(HttpResponse::new(200, r#"<div class="result-container">Hallo</div>"#))
+            }
+        }
+
+        let mut translator = Translator::builder()
+            .strategy(Strategy::Single("google.API_MobileGoogleTranslate".to_string()))
+            .transport(Offline)
+            .build()
+            .unwrap();
+        let translation = translator.translate("Hello", Language::English, Language::German).await.unwrap();
+        assert_eq!(translation.result(), "Hallo");
+        assert_eq!(translation.attempts().len(), 1);
+    }
+
     #[test]
     fn test_detector_builder() {
         use crate::Error;
         use crate::Strategy;
         use crate::Detector;
+        use crate::RateLimit;
 
         let _ = Detector::builder().build();
 
@@ -251,5 +788,34 @@ mod tests {
             .build(),
             Err(Error::InvalidServiceName))
         );
+
+        assert!(matches!(Detector::builder()
+            .rate_limit("google.API_MobileGoogleTranslate", RateLimit::new().requests_per_minute(10))
+            .build(),
+            Err(Error::InvalidServiceName))
+        );
+
+        let mut detector = Detector::builder()
+            .rate_limit("google.API_GoogleTranslateExtensions", RateLimit::new().daily_chars(1_000))
+            .build()
+            .unwrap();
+        let quotas = detector.quotas();
+        assert_eq!(quotas.len(), 1);
+        assert_eq!(quotas[0].1.daily_chars(), Some(1_000));
+        assert_eq!(quotas[0].1.chars_per_minute(), None);
+
+        let path = std::env::temp_dir().join(format!("libtranslate-state-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(Detector::builder().load_state(&path).build().is_ok());
+        detector.save_state(&path).unwrap();
+        let mut restored = Detector::builder()
+            .rate_limit("google.API_GoogleTranslateExtensions", RateLimit::new().daily_chars(1_000))
+            .load_state(&path)
+            .build()
+            .unwrap();
+        assert_eq!(restored.services().len(), 2);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(Detector::builder().load_state(&path).build(), Err(Error::JSONParsingError(_))));
+        std::fs::remove_file(&path).unwrap();
     }
 }
\ No newline at end of file